@@ -0,0 +1,137 @@
+// Compares the recursive solver the game used to ship with against `Solver`
+// Run with `cargo bench`, the dictionary is read from DICTIONARY_PATH
+use boggle_game::models::{Board, BoardSize, Dictionary, SearchResult, Solver, Tile};
+use std::{
+    collections::HashSet,
+    env,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+// Number of seeded boards solved for every board size
+const BOARDS: u64 = 200;
+
+// The previous solver: a `Vec<Vec<bool>>` visited set, a trie search from the root
+// on every step and a linear scan of the found words to skip duplicates
+struct LegacySolver<'a> {
+    dictionary: &'a Dictionary,
+    board: &'a [Vec<Tile>],
+    words: Vec<(String, String)>,
+}
+
+impl<'a> LegacySolver<'a> {
+    fn solve(dictionary: &'a Dictionary, board: &'a [Vec<Tile>]) -> Vec<(String, String)> {
+        let mut solver = Self {
+            dictionary,
+            board,
+            words: Vec::new(),
+        };
+        let size = board.len();
+        let mut visited = vec![vec![false; size]; size];
+        let mut current_word = String::new();
+
+        for i in 0..size {
+            for j in 0..size {
+                solver.dfs(i, j, &mut visited, &mut current_word);
+            }
+        }
+        solver.words
+    }
+
+    fn dfs(&mut self, i: usize, j: usize, visited: &mut Vec<Vec<bool>>, current_word: &mut String) {
+        let size = self.board.len();
+        if i >= size || j >= size || visited[i][j] {
+            return;
+        }
+
+        visited[i][j] = true;
+        let word_length = current_word.len();
+        current_word.push_str(self.board[i][j].letters());
+
+        match self.dictionary.search(&current_word.to_lowercase()) {
+            SearchResult::ValidWord(definition) => {
+                if !self.words.iter().any(|(w, _)| w == current_word) {
+                    self.words.push((current_word.clone(), definition));
+                }
+                self.continue_search(i, j, visited, current_word);
+            }
+            SearchResult::ValidPrefix => self.continue_search(i, j, visited, current_word),
+            SearchResult::NotFound => {}
+        }
+
+        current_word.truncate(word_length);
+        visited[i][j] = false;
+    }
+
+    fn continue_search(
+        &mut self,
+        i: usize,
+        j: usize,
+        visited: &mut Vec<Vec<bool>>,
+        current_word: &mut String,
+    ) {
+        let size = self.board.len() as isize;
+        let row_offsets = [-1, -1, -1, 0, 0, 1, 1, 1];
+        let col_offsets = [-1, 0, 1, -1, 1, -1, 0, 1];
+
+        for k in 0..8 {
+            let new_i = i as isize + row_offsets[k];
+            let new_j = j as isize + col_offsets[k];
+            if new_i >= 0 && new_i < size && new_j >= 0 && new_j < size {
+                self.dfs(new_i as usize, new_j as usize, visited, current_word);
+            }
+        }
+    }
+}
+
+fn time<F: FnMut()>(mut f: F) -> Duration {
+    let start = Instant::now();
+    f();
+    start.elapsed()
+}
+
+fn main() {
+    let path = env::var("DICTIONARY_PATH")
+        .unwrap_or_else(|_| "static/scrabble-dictionary.txt".to_string());
+    let dictionary = Arc::new(Dictionary::new(&path).expect("Failed to create dictionary"));
+
+    println!("Solving {} boards per size", BOARDS);
+    for size in BoardSize::ALL {
+        let boards: Vec<Board> = (0..BOARDS)
+            .map(|seed| Board::new(&dictionary, size.default_dice(), seed))
+            .collect();
+
+        // Both solvers have to agree before their timings mean anything
+        for board in &boards {
+            let legacy: HashSet<String> = LegacySolver::solve(&dictionary, &board.board)
+                .into_iter()
+                .map(|(word, _)| word)
+                .collect();
+            let current: HashSet<String> = Solver::new(&dictionary, &board.board)
+                .solve()
+                .iter()
+                .map(|found| found.word.clone())
+                .collect();
+            assert_eq!(legacy, current, "Solvers disagree on {}", board.notation());
+        }
+
+        let legacy = time(|| {
+            for board in &boards {
+                LegacySolver::solve(&dictionary, &board.board);
+            }
+        });
+        let current = time(|| {
+            for board in &boards {
+                Solver::new(&dictionary, &board.board).solve();
+            }
+        });
+
+        println!(
+            "{}: legacy {:>10.2?}  solver {:>10.2?}  {:.1}x faster",
+            size.label(),
+            legacy,
+            current,
+            legacy.as_secs_f64() / current.as_secs_f64()
+        );
+    }
+}
@@ -0,0 +1,225 @@
+// Solve a board from the command line without starting the web server
+//
+//   boggle-solve --board ABCD/EFGH/IJKL/MNQuP
+//   boggle-solve --seed 42 --size big --json
+//   boggle-solve --dice original --scoring custom --points 1,2,3,5,8
+use boggle_game::models::{Board, BoardSize, DiceSet, Dictionary, FormValue, Scoring};
+use serde_json::json;
+use std::{env, process, sync::Arc};
+
+const USAGE: &str = "Usage: boggle-solve [OPTIONS]
+
+Options:
+  --board <NOTATION>   Solve this board, e.g. ABCD/EFGH/IJKL/MNQuP
+  --seed <SEED>        Roll the board from this seed (random if neither is given)
+  --size <SIZE>        Size of a rolled board: classic, big or superbig [default: classic]
+  --dice <DICE>        Dice to roll: classic, original, big or superbig [default: the size's dice]
+  --scoring <RULE>     classic, bigboggle, scrabble, rarity or custom [default: classic]
+  --points <POINTS>    Points by word length from three letters for custom scoring, e.g. 1,1,2,3,5,11
+  --dictionary <PATH>  Dictionary file [default: $STATIC_FILES_PATH/scrabble-dictionary.txt]
+  --json               Print the result as JSON
+  -h, --help           Print this help";
+
+struct Options {
+    board: Option<String>,
+    seed: Option<u64>,
+    size: Option<BoardSize>,
+    dice: Option<DiceSet>,
+    scoring: Scoring,
+    dictionary: String,
+    json: bool,
+}
+
+impl Options {
+    fn parse() -> Result<Self, String> {
+        let mut options = Options {
+            board: None,
+            seed: None,
+            size: None,
+            dice: None,
+            scoring: Scoring::default(),
+            dictionary: Dictionary::default_path(),
+            json: false,
+        };
+        let mut scoring = None;
+        let mut points = None;
+
+        let mut args = env::args().skip(1);
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or(format!("Missing value for {}", arg));
+            match arg.as_str() {
+                "--board" => options.board = Some(value()?),
+                "--seed" => {
+                    let seed = value()?;
+                    options.seed = Some(
+                        seed.parse()
+                            .map_err(|_| format!("Invalid seed: {}", seed))?,
+                    )
+                }
+                "--size" => {
+                    let size = value()?;
+                    options.size = Some(
+                        BoardSize::ALL
+                            .into_iter()
+                            .find(|s| s.value() == size || s.label() == size)
+                            .ok_or(format!("Invalid size: {}", size))?,
+                    )
+                }
+                "--dice" => {
+                    let dice = value()?;
+                    options.dice = Some(
+                        DiceSet::ALL
+                            .into_iter()
+                            .find(|d| d.value() == dice)
+                            .ok_or(format!("Invalid dice: {}", dice))?,
+                    )
+                }
+                "--scoring" => {
+                    let rule = value()?;
+                    if !Scoring::ALL.iter().any(|s| s.value() == rule) {
+                        return Err(format!("Invalid scoring: {}", rule));
+                    }
+                    scoring = Some(rule)
+                }
+                "--points" => points = Some(value()?),
+                "--dictionary" => options.dictionary = value()?,
+                "--json" => options.json = true,
+                "-h" | "--help" => {
+                    println!("{}", USAGE);
+                    process::exit(0);
+                }
+                _ => return Err(format!("Unknown argument: {}", arg)),
+            }
+        }
+
+        // A board given in full has nothing left to roll
+        if options.board.is_some()
+            && (options.seed.is_some() || options.size.is_some() || options.dice.is_some())
+        {
+            return Err("--board can't be used with --seed, --size or --dice".to_string());
+        }
+        if let (Some(size), Some(dice)) = (options.size, options.dice) {
+            if dice.size() != size {
+                return Err(format!(
+                    "{} dice don't fit a {} board",
+                    dice.value(),
+                    size.value()
+                ));
+            }
+        }
+
+        match (scoring.as_deref(), points.as_deref()) {
+            (Some("custom"), None) => return Err("Custom scoring needs --points".to_string()),
+            (Some("custom"), Some(points)) => {
+                options.scoring = Scoring::from_form("custom", points).map_err(|e| e.to_string())?
+            }
+            (_, Some(_)) => return Err("--points only applies to custom scoring".to_string()),
+            (Some(rule), None) => {
+                options.scoring = Scoring::from_form(rule, "").map_err(|e| e.to_string())?
+            }
+            (None, None) => (),
+        }
+
+        Ok(options)
+    }
+}
+
+fn print_text(board: &Board) {
+    println!("Board: {}", board.notation());
+    if let Some(seed) = board.seed {
+        println!("Seed: {}", seed);
+    }
+    println!();
+    for row in &board.board {
+        let row: Vec<String> = row.iter().map(|tile| format!("{:<2}", tile)).collect();
+        println!("  {}", row.join(" ").trim_end());
+    }
+    println!();
+    println!(
+        "{} words, {} points",
+        board.words.len(),
+        board.words.total_score
+    );
+
+    let width = board
+        .words
+        .iter()
+        .map(|found| found.word.len())
+        .max()
+        .unwrap_or(0);
+    for found in board.words.iter() {
+        let path: Vec<String> = found
+            .path
+            .iter()
+            .map(|(row, col)| format!("{},{}", row, col))
+            .collect();
+        println!(
+            "{:<width$}  {:>2}  {}  {}",
+            found.word,
+            found.points,
+            path.join(" "),
+            found.definition,
+            width = width
+        );
+    }
+}
+
+fn print_json(board: &Board) {
+    let words: Vec<_> = board
+        .words
+        .iter()
+        .map(|found| {
+            json!({
+                "word": found.word,
+                "score": found.points,
+                "path": found.path,
+                "definition": found.definition,
+            })
+        })
+        .collect();
+
+    let output = json!({
+        "board": board.notation(),
+        "seed": board.seed,
+        "size": board.size,
+        "total_score": board.words.total_score,
+        "words": words,
+    });
+    println!("{:#}", output);
+}
+
+fn main() {
+    let options = Options::parse().unwrap_or_else(|e| {
+        eprintln!("{}\n\n{}", e, USAGE);
+        process::exit(2);
+    });
+
+    let dictionary = match Dictionary::new(&options.dictionary) {
+        Ok(dictionary) => Arc::new(dictionary),
+        Err(e) => {
+            eprintln!("Failed to load dictionary {}: {}", options.dictionary, e);
+            process::exit(1);
+        }
+    };
+
+    let board = match &options.board {
+        Some(notation) => Board::from_notation(&dictionary, notation).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            process::exit(1);
+        }),
+        None => Board::new(
+            &dictionary,
+            options
+                .dice
+                .unwrap_or_else(|| options.size.unwrap_or_default().default_dice()),
+            options.seed.unwrap_or_else(Board::random_seed),
+        ),
+    }
+    .with_min_word_length(Board::MIN_WORD_LENGTH, &options.scoring);
+
+    if options.json {
+        print_json(&board);
+    } else {
+        print_text(&board);
+    }
+}
@@ -1,41 +1,115 @@
 use axum::{
-    extract::{ws::WebSocketUpgrade, Form, State},
+    extract::{ws::WebSocketUpgrade, Form, Path, Query, State},
     http::StatusCode,
-    response::{Html, IntoResponse},
-    Extension,
+    response::{Html, IntoResponse, Response},
 };
 use uuid::Uuid;
 
 use std::{
+    fmt,
+    str::FromStr,
     sync::Arc,
     time::{SystemTime, UNIX_EPOCH},
 };
 use tokio::sync::Mutex;
 use tower_sessions::Session;
 
-use crate::models::{Boggle, PlayerIdSubmission};
+use crate::models::{
+    BoardPolicy, BoardSetup, BoardSize, Boggle, ChallengeId, DiceSet, Difficulty, GameSettings,
+    HintKind, PlayerIdSubmission, RoomCode, Rooms, Scoreboard, Scoring, ScoringMode, SettingsError,
+};
 use crate::render::Render;
 use crate::{handlers::WebSockets, models::PlayerId};
-use serde::Deserialize;
+use serde::{de, Deserialize, Deserializer};
 
 #[derive(Deserialize, Debug)]
 pub struct WordSubmission {
     word: String,
 }
+
+#[derive(Deserialize, Debug)]
+pub struct JoinSubmission {
+    code: RoomCode,
+    #[serde(default)]
+    spectate: bool,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct RoomQuery {
+    #[serde(default)]
+    spectate: bool,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct NewGameSubmission {
+    #[serde(default, deserialize_with = "empty_as_none")]
+    seed: Option<u64>,
+    #[serde(default)]
+    board: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct PlayerSubmission {
+    player: PlayerId,
+    #[serde(default)]
+    ban: bool,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct BotSubmission {
+    #[serde(default)]
+    difficulty: Difficulty,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct HintSubmission {
+    kind: HintKind,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct SettingsSubmission {
+    duration: u32,
+    min_word_length: usize,
+    size: BoardSize,
+    dice: DiceSet,
+    dictionary: String,
+    scoring_mode: ScoringMode,
+    scoring: String,
+    scoreboard: Scoreboard,
+    rounds: u32,
+    teams: u32,
+    hints: u32,
+    #[serde(default)]
+    custom_points: String,
+    #[serde(default, deserialize_with = "empty_as_none")]
+    min_words: Option<usize>,
+    #[serde(default, deserialize_with = "empty_as_none")]
+    max_words: Option<usize>,
+    #[serde(default, deserialize_with = "empty_as_none")]
+    min_score: Option<u32>,
+    #[serde(default, deserialize_with = "empty_as_none")]
+    long_word: Option<usize>,
+}
+
+// Optional number inputs are submitted as empty strings when left blank
+fn empty_as_none<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    let value = String::deserialize(deserializer)?;
+    match value.trim() {
+        "" => Ok(None),
+        value => value.parse().map(Some).map_err(de::Error::custom),
+    }
+}
+
 pub struct Handle {}
 
 impl Handle {
     pub async fn root(session: Session) -> impl IntoResponse {
-        Self::update_last_seen(&session).await;
-
-        if session.get::<String>("id").await.unwrap_or(None).is_none() {
-            // Generate a new UUID and store it in the session
-            let new_id = Uuid::new_v4().to_string();
-            session
-                .insert("id", &new_id)
-                .await
-                .expect("Failed to insert new ID into session");
-        }
+        Self::start_session(&session).await;
 
         match session.get::<String>("username").await {
             Ok(Some(username)) => {
@@ -46,6 +120,31 @@ impl Handle {
         }
     }
 
+    pub async fn room(
+        session: Session,
+        State(rooms): State<Arc<Mutex<Rooms>>>,
+        Path(code): Path<RoomCode>,
+        Query(RoomQuery { spectate }): Query<RoomQuery>,
+    ) -> impl IntoResponse {
+        let code = code.normalize();
+        if !rooms.lock().await.contains(&code) {
+            return (StatusCode::NOT_FOUND, Html(Render::room_missing(&code))).into_response();
+        }
+
+        // Room links can be opened directly, so this can be the first page a player sees
+        Self::start_session(&session).await;
+
+        // Spectators don't need a name since they never show up in the room
+        if spectate {
+            return Html(Render::room(&code, true)).into_response();
+        }
+
+        match session.get::<String>("username").await {
+            Ok(Some(_)) => Html(Render::room(&code, false)).into_response(),
+            _ => Html(Render::root_no_username()).into_response(),
+        }
+    }
+
     pub async fn username(
         session: Session,
         Form(PlayerIdSubmission { username }): Form<PlayerIdSubmission>,
@@ -54,19 +153,60 @@ impl Handle {
             return (StatusCode::INTERNAL_SERVER_ERROR, "Could not serialize.").into_response();
         }
 
-        Html(Render::shell_template()).into_response()
+        // Reload the page the form was on, now that the player has a name
+        ([("HX-Refresh", "true")], StatusCode::OK).into_response()
+    }
+
+    pub async fn create_room(State(rooms): State<Arc<Mutex<Rooms>>>) -> impl IntoResponse {
+        let code = rooms.lock().await.create();
+        Self::redirect_to_room(&code, false)
+    }
+
+    pub async fn create_solo_room(
+        session: Session,
+        State(rooms): State<Arc<Mutex<Rooms>>>,
+    ) -> impl IntoResponse {
+        let player_id = Self::player_id(&session).await;
+        let code = rooms.lock().await.create_solo(player_id);
+        Self::redirect_to_room(&code, false)
+    }
+
+    pub async fn create_daily_room(
+        session: Session,
+        State(rooms): State<Arc<Mutex<Rooms>>>,
+    ) -> impl IntoResponse {
+        let player_id = Self::player_id(&session).await;
+        let code = rooms.lock().await.create_daily(player_id);
+        Self::redirect_to_room(&code, false)
+    }
+
+    // Solo rooms can't be joined by code
+    pub async fn join_room(
+        State(rooms): State<Arc<Mutex<Rooms>>>,
+        Form(JoinSubmission { code, spectate }): Form<JoinSubmission>,
+    ) -> impl IntoResponse {
+        let code = code.normalize();
+        let boggle = rooms.lock().await.get(&code);
+        match boggle {
+            Some(boggle) if !boggle.lock().await.is_solo() => {
+                Self::redirect_to_room(&code, spectate)
+            }
+            _ => Html(Render::room_not_found(&code)).into_response(),
+        }
     }
 
     pub async fn submit_word(
         session: Session,
-        Extension(boggle): Extension<Arc<Mutex<Boggle>>>,
+        State(rooms): State<Arc<Mutex<Rooms>>>,
+        Path(code): Path<RoomCode>,
         Form(WordSubmission { word }): Form<WordSubmission>,
     ) -> impl IntoResponse {
-        let player_id = session
-            .get::<PlayerId>("id")
-            .await
-            .expect("Could not deserialize.")
-            .unwrap();
+        let boggle = match Self::find_room(&rooms, &code).await {
+            Ok(boggle) => boggle,
+            Err(response) => return response,
+        };
+
+        let player_id = Self::player_id(&session).await;
 
         let mut boggle = boggle.lock().await;
         let word_submission_html = boggle.submit_word(&player_id, &word);
@@ -74,29 +214,437 @@ impl Handle {
         Html(word_submission_html).into_response()
     }
 
-    pub async fn new_game(Extension(boggle): Extension<Arc<Mutex<Boggle>>>) -> impl IntoResponse {
+    pub async fn new_game(
+        session: Session,
+        State(rooms): State<Arc<Mutex<Rooms>>>,
+        Path(code): Path<RoomCode>,
+        Form(submission): Form<NewGameSubmission>,
+    ) -> impl IntoResponse {
+        let boggle = match Self::find_room(&rooms, &code).await {
+            Ok(boggle) => boggle,
+            Err(response) => return response,
+        };
+
+        let setup = {
+            let boggle = boggle.lock().await;
+            if let Err(response) = Self::require_host(&boggle, &session).await {
+                return response;
+            }
+
+            if !submission.board.trim().is_empty() {
+                BoardSetup::Notation(submission.board)
+            } else {
+                boggle.settings.roll(submission.seed)
+            }
+        };
+
+        // Reset the game state
+        if let Err(e) = Boggle::new_game(&boggle, &setup).await {
+            return (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                Html(Render::setup_error(&e.to_string())),
+            )
+                .into_response();
+        }
+        (StatusCode::NO_CONTENT, ()).into_response()
+    }
+
+    pub async fn toggle_ready(
+        session: Session,
+        State(rooms): State<Arc<Mutex<Rooms>>>,
+        Path(code): Path<RoomCode>,
+    ) -> impl IntoResponse {
+        let boggle = match Self::find_room(&rooms, &code).await {
+            Ok(boggle) => boggle,
+            Err(response) => return response,
+        };
+
+        let player_id = Self::player_id(&session).await;
+
+        let ready = boggle.lock().await.toggle_ready(&player_id);
+        Boggle::start_when_ready(&boggle).await;
+
+        Html(Render::ready_button(&code.normalize(), ready)).into_response()
+    }
+
+    pub async fn switch_team(
+        session: Session,
+        State(rooms): State<Arc<Mutex<Rooms>>>,
+        Path(code): Path<RoomCode>,
+    ) -> impl IntoResponse {
+        let boggle = match Self::find_room(&rooms, &code).await {
+            Ok(boggle) => boggle,
+            Err(response) => return response,
+        };
+
+        let player_id = Self::player_id(&session).await;
+
+        // The new teams reach every player over the websocket
+        if boggle.lock().await.switch_team(&player_id) {
+            (StatusCode::NO_CONTENT, ()).into_response()
+        } else {
+            (StatusCode::CONFLICT, "Teams can only change between rounds").into_response()
+        }
+    }
+
+    pub async fn update_settings(
+        session: Session,
+        State(rooms): State<Arc<Mutex<Rooms>>>,
+        Path(code): Path<RoomCode>,
+        Form(submission): Form<SettingsSubmission>,
+    ) -> impl IntoResponse {
+        let boggle = match Self::find_room(&rooms, &code).await {
+            Ok(boggle) => boggle,
+            Err(response) => return response,
+        };
+
+        let scoring = Scoring::from_form(&submission.scoring, &submission.custom_points);
+        let settings = scoring.map(|scoring| GameSettings {
+            duration: submission.duration,
+            min_word_length: submission.min_word_length,
+            board_size: submission.size,
+            dice: submission.dice,
+            dictionary: submission.dictionary,
+            board_policy: BoardPolicy {
+                min_words: submission.min_words,
+                max_words: submission.max_words,
+                min_score: submission.min_score,
+                long_word: submission.long_word,
+            },
+            scoring_mode: submission.scoring_mode,
+            scoring,
+            scoreboard: submission.scoreboard,
+            rounds: submission.rounds,
+            teams: submission.teams,
+            hints: submission.hints,
+        });
+
+        let mut boggle = boggle.lock().await;
+        if let Err(response) = Self::require_host(&boggle, &session).await {
+            return response;
+        }
+
+        // The updated settings reach every player over the websocket
+        match settings.and_then(|settings| boggle.update_settings(settings)) {
+            Ok(()) => (StatusCode::NO_CONTENT, ()).into_response(),
+            Err(SettingsError::Locked) => {
+                (StatusCode::CONFLICT, SettingsError::Locked.to_string()).into_response()
+            }
+            Err(e) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                Html(Render::setup_error(&e.to_string())),
+            )
+                .into_response(),
+        }
+    }
+
+    pub async fn pause(
+        session: Session,
+        State(rooms): State<Arc<Mutex<Rooms>>>,
+        Path(code): Path<RoomCode>,
+    ) -> impl IntoResponse {
+        let boggle = match Self::find_room(&rooms, &code).await {
+            Ok(boggle) => boggle,
+            Err(response) => return response,
+        };
+
+        let mut boggle = boggle.lock().await;
+        if let Err(response) = Self::require_host(&boggle, &session).await {
+            return response;
+        }
+
+        if boggle.pause().await {
+            (StatusCode::NO_CONTENT, ()).into_response()
+        } else {
+            (StatusCode::CONFLICT, "No round to pause").into_response()
+        }
+    }
+
+    pub async fn end_round(
+        session: Session,
+        State(rooms): State<Arc<Mutex<Rooms>>>,
+        Path(code): Path<RoomCode>,
+    ) -> impl IntoResponse {
+        let boggle = match Self::find_room(&rooms, &code).await {
+            Ok(boggle) => boggle,
+            Err(response) => return response,
+        };
+
         let mut boggle = boggle.lock().await;
+        if let Err(response) = Self::require_host(&boggle, &session).await {
+            return response;
+        }
 
-        boggle.new_game().await; // Reset the game state
-        (StatusCode::NO_CONTENT, ())
+        if boggle.end_round().await {
+            (StatusCode::NO_CONTENT, ()).into_response()
+        } else {
+            (StatusCode::CONFLICT, "No round to end").into_response()
+        }
+    }
+
+    // Turns the round the player just finished into a link a friend can play later
+    pub async fn create_challenge(
+        session: Session,
+        State(rooms): State<Arc<Mutex<Rooms>>>,
+        Path(code): Path<RoomCode>,
+    ) -> impl IntoResponse {
+        let boggle = match Self::find_room(&rooms, &code).await {
+            Ok(boggle) => boggle,
+            Err(response) => return response,
+        };
+
+        let player_id = Self::player_id(&session).await;
+        let challenge = {
+            let boggle = boggle.lock().await;
+            if let Some(id) = boggle.challenge_sent(&player_id) {
+                return Html(Render::challenge_link(&id)).into_response();
+            }
+            boggle.challenge_from(&player_id)
+        };
+        let Some(challenge) = challenge else {
+            return (StatusCode::CONFLICT, "No finished round to share").into_response();
+        };
+
+        let challenges = rooms.lock().await.challenges();
+        let id = challenges
+            .lock()
+            .expect("Challenges lock poisoned")
+            .add(challenge);
+        let id = boggle.lock().await.record_challenge(&player_id, id);
+        Html(Render::challenge_link(&id)).into_response()
+    }
+
+    pub async fn challenge(
+        session: Session,
+        State(rooms): State<Arc<Mutex<Rooms>>>,
+        Path(id): Path<ChallengeId>,
+    ) -> impl IntoResponse {
+        let challenges = rooms.lock().await.challenges();
+        let challenge = challenges
+            .lock()
+            .ok()
+            .and_then(|challenges| challenges.get(&id).cloned());
+        let Some(challenge) = challenge else {
+            return (StatusCode::NOT_FOUND, Html(Render::challenge_missing())).into_response();
+        };
+
+        // Challenge links are shared, so this can be the first page a player sees
+        Self::start_session(&session).await;
+
+        match session.get::<String>("username").await {
+            Ok(Some(_)) => Html(Render::challenge_page(&id, &challenge)).into_response(),
+            _ => Html(Render::root_no_username()).into_response(),
+        }
+    }
+
+    // Each attempt gets its own private room with the challenge's board
+    pub async fn accept_challenge(
+        session: Session,
+        State(rooms): State<Arc<Mutex<Rooms>>>,
+        Path(id): Path<ChallengeId>,
+    ) -> impl IntoResponse {
+        let player_id = Self::player_id(&session).await;
+        match rooms.lock().await.create_challenge(player_id, id) {
+            Some(code) => Self::redirect_to_room(&code, false),
+            None => (StatusCode::NOT_FOUND, Html(Render::challenge_missing())).into_response(),
+        }
+    }
+
+    pub async fn request_hint(
+        session: Session,
+        State(rooms): State<Arc<Mutex<Rooms>>>,
+        Path(code): Path<RoomCode>,
+        Form(HintSubmission { kind }): Form<HintSubmission>,
+    ) -> impl IntoResponse {
+        let boggle = match Self::find_room(&rooms, &code).await {
+            Ok(boggle) => boggle,
+            Err(response) => return response,
+        };
+
+        let player_id = Self::player_id(&session).await;
+        let hints_html = boggle.lock().await.request_hint(&player_id, kind);
+        match hints_html {
+            Some(hints_html) => Html(hints_html).into_response(),
+            None => (StatusCode::CONFLICT, "No hint available").into_response(),
+        }
+    }
+
+    pub async fn resume(
+        session: Session,
+        State(rooms): State<Arc<Mutex<Rooms>>>,
+        Path(code): Path<RoomCode>,
+    ) -> impl IntoResponse {
+        let boggle = match Self::find_room(&rooms, &code).await {
+            Ok(boggle) => boggle,
+            Err(response) => return response,
+        };
+
+        let mut boggle = boggle.lock().await;
+        if let Err(response) = Self::require_host(&boggle, &session).await {
+            return response;
+        }
+
+        if boggle.resume().await {
+            (StatusCode::NO_CONTENT, ()).into_response()
+        } else {
+            (StatusCode::CONFLICT, "The round isn't paused").into_response()
+        }
+    }
+
+    pub async fn kick(
+        session: Session,
+        State(rooms): State<Arc<Mutex<Rooms>>>,
+        Path(code): Path<RoomCode>,
+        Form(PlayerSubmission { player, ban }): Form<PlayerSubmission>,
+    ) -> impl IntoResponse {
+        let boggle = match Self::find_room(&rooms, &code).await {
+            Ok(boggle) => boggle,
+            Err(response) => return response,
+        };
+
+        let mut boggle = boggle.lock().await;
+        if let Err(response) = Self::require_host(&boggle, &session).await {
+            return response;
+        }
+
+        if boggle.kick(&player, ban) {
+            (StatusCode::NO_CONTENT, ()).into_response()
+        } else {
+            (StatusCode::NOT_FOUND, "No player to remove").into_response()
+        }
+    }
+
+    pub async fn add_bot(
+        session: Session,
+        State(rooms): State<Arc<Mutex<Rooms>>>,
+        Path(code): Path<RoomCode>,
+        Form(BotSubmission { difficulty }): Form<BotSubmission>,
+    ) -> impl IntoResponse {
+        let boggle = match Self::find_room(&rooms, &code).await {
+            Ok(boggle) => boggle,
+            Err(response) => return response,
+        };
+
+        let mut boggle = boggle.lock().await;
+        if let Err(response) = Self::require_host(&boggle, &session).await {
+            return response;
+        }
+
+        if boggle.add_bot(difficulty) {
+            (StatusCode::NO_CONTENT, ()).into_response()
+        } else {
+            (
+                StatusCode::CONFLICT,
+                "Bots can only join between rounds, up to the limit",
+            )
+                .into_response()
+        }
+    }
+
+    pub async fn transfer_host(
+        session: Session,
+        State(rooms): State<Arc<Mutex<Rooms>>>,
+        Path(code): Path<RoomCode>,
+        Form(PlayerSubmission { player, .. }): Form<PlayerSubmission>,
+    ) -> impl IntoResponse {
+        let boggle = match Self::find_room(&rooms, &code).await {
+            Ok(boggle) => boggle,
+            Err(response) => return response,
+        };
+
+        let mut boggle = boggle.lock().await;
+        if let Err(response) = Self::require_host(&boggle, &session).await {
+            return response;
+        }
+
+        if boggle.transfer_host(&player) {
+            (StatusCode::NO_CONTENT, ()).into_response()
+        } else {
+            (StatusCode::NOT_FOUND, "Player isn't connected").into_response()
+        }
     }
 
     pub async fn get_player_score(
-        Extension(boggle): Extension<Arc<Mutex<Boggle>>>,
+        session: Session,
+        State(rooms): State<Arc<Mutex<Rooms>>>,
+        Path(code): Path<RoomCode>,
         Form(PlayerIdSubmission { username }): Form<PlayerIdSubmission>,
     ) -> impl IntoResponse {
-        let boggle = boggle.lock().await;
-        let player_score_html = boggle.get_player_score(username).await;
+        let boggle = match Self::find_room(&rooms, &code).await {
+            Ok(boggle) => boggle,
+            Err(response) => return response,
+        };
 
-        Html(player_score_html).into_response()
+        let player_id = Self::player_id(&session).await;
+        let player_score_html = boggle.lock().await.get_player_score(&player_id, username);
+        match player_score_html {
+            Some(player_score_html) => Html(player_score_html).into_response(),
+            None => (StatusCode::FORBIDDEN, "Those words aren't yours to see yet").into_response(),
+        }
     }
 
     pub async fn websocket(
         ws: WebSocketUpgrade,
-        State(state): State<Arc<Mutex<Boggle>>>,
+        State(rooms): State<Arc<Mutex<Rooms>>>,
+        Path(code): Path<RoomCode>,
+        Query(RoomQuery { spectate }): Query<RoomQuery>,
         session: Session,
     ) -> impl IntoResponse {
-        ws.on_upgrade(|socket| async move { WebSockets::new(socket, state, session).await })
+        let boggle = match Self::find_room(&rooms, &code).await {
+            Ok(boggle) => boggle,
+            Err(response) => return response,
+        };
+
+        ws.on_upgrade(move |socket| async move {
+            WebSockets::new(socket, rooms, boggle, session, spectate).await
+        })
+        .into_response()
+    }
+
+    async fn find_room(
+        rooms: &Arc<Mutex<Rooms>>,
+        code: &RoomCode,
+    ) -> Result<Arc<Mutex<Boggle>>, Response> {
+        rooms.lock().await.get(code).ok_or_else(|| {
+            let code = code.normalize();
+            (StatusCode::NOT_FOUND, Html(Render::room_not_found(&code))).into_response()
+        })
+    }
+
+    // Any route can be posted to without loading a page first, so the session
+    // gets its id here if it doesn't have one yet
+    async fn player_id(session: &Session) -> PlayerId {
+        if let Ok(Some(player_id)) = session.get::<PlayerId>("id").await {
+            return player_id;
+        }
+
+        let player_id = PlayerId(Uuid::new_v4().to_string());
+        if let Err(e) = session.insert("id", &player_id).await {
+            eprintln!("Failed to insert new ID into session: {}", e);
+        }
+        player_id
+    }
+
+    async fn require_host(boggle: &Boggle, session: &Session) -> Result<(), Response> {
+        if boggle.is_host(&Self::player_id(session).await) {
+            Ok(())
+        } else {
+            Err((StatusCode::FORBIDDEN, "Only the host can do that").into_response())
+        }
+    }
+
+    fn redirect_to_room(code: &RoomCode, spectate: bool) -> Response {
+        let location = if spectate {
+            format!("/room/{}?spectate=true", code)
+        } else {
+            format!("/room/{}", code)
+        };
+        ([("HX-Redirect", location)], StatusCode::OK).into_response()
+    }
+
+    async fn start_session(session: &Session) {
+        Self::update_last_seen(session).await;
+        Self::player_id(session).await;
     }
 
     async fn update_last_seen(session: &Session) {
@@ -1,3 +1,4 @@
+#[allow(clippy::module_inception)]
 mod handlers;
 mod websockets;
 pub use handlers::Handle;
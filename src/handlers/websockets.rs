@@ -7,6 +7,7 @@ use futures::{
 use std::sync::Arc;
 use tokio::{
     sync::{
+        broadcast,
         mpsc::{UnboundedReceiver, UnboundedSender},
         Mutex,
     },
@@ -14,13 +15,20 @@ use tokio::{
 };
 use tower_sessions::Session;
 
-use crate::models::{Boggle, PlayerId};
+use crate::models::{Boggle, PlayerId, Rooms};
 use crate::render::Render;
 
 pub struct WebSockets {}
 
 impl WebSockets {
-    pub async fn new(ws: WebSocket, boggle: Arc<Mutex<Boggle>>, session: Session) {
+    #[allow(clippy::new_ret_no_self)]
+    pub async fn new(
+        ws: WebSocket,
+        rooms: Arc<Mutex<Rooms>>,
+        boggle: Arc<Mutex<Boggle>>,
+        session: Session,
+        spectate: bool,
+    ) {
         //Broadcast tx/rx
         let (sender, receiver) = ws.split();
         //Direct tx/rx
@@ -28,12 +36,19 @@ impl WebSockets {
 
         Self::spawn_sender_task(ws_receiver, sender).await;
 
-        let player_id_opt = Self::handle_user_connection(&ws_sender, &boggle, &session).await;
+        let player_id_opt = if spectate {
+            Self::handle_spectator_connection(&ws_sender, &boggle, &session).await
+        } else {
+            Self::handle_user_connection(&ws_sender, &boggle, &session).await
+        };
 
         match player_id_opt {
             Some(player_id) => {
                 Self::send_initial_game_boggle(&ws_sender, &boggle, &player_id).await;
-                Self::monitor_websocket_connection(receiver, &ws_sender, boggle, player_id).await;
+                Self::monitor_websocket_connection(
+                    receiver, &ws_sender, rooms, boggle, player_id, spectate,
+                )
+                .await;
             }
             None => {
                 ws_sender.send(Message::Close(None)).unwrap();
@@ -57,8 +72,10 @@ impl WebSockets {
     async fn monitor_websocket_connection(
         receiver: SplitStream<WebSocket>,
         ws_sender: &UnboundedSender<Message>,
+        rooms: Arc<Mutex<Rooms>>,
         boggle: Arc<Mutex<Boggle>>,
         username: PlayerId,
+        spectate: bool,
     ) {
         //Sends game messages (html) to all users
         let mut send_task =
@@ -82,7 +99,11 @@ impl WebSockets {
             result = (&mut recv_task) => handle_task_completion("Receive", &mut send_task, result),
         };
 
-        Self::cleanup(&boggle, &username).await;
+        if spectate {
+            boggle.lock().await.stop_watching(&username);
+        } else {
+            Self::cleanup(rooms, &boggle, &username).await;
+        }
     }
 
     async fn handle_user_connection(
@@ -96,19 +117,45 @@ impl WebSockets {
         ) {
             (Ok(Some(player_id)), Ok(Some(username))) => {
                 let mut boggle = boggle.lock().await;
-                if !boggle.players.contains_key(&player_id) {
-                    boggle.players.add_player(
-                        player_id.clone(),
-                        ws_sender.clone(),
-                        username.clone(),
-                    );
+                if boggle.join(&player_id, ws_sender.clone(), username) {
+                    Some(player_id)
+                } else if boggle.is_solo() {
+                    let _ = ws_sender.send(Message::Text(Render::private_room()));
+                    None
+                } else {
+                    let _ = ws_sender.send(Message::Text(Render::removed(true)));
+                    None
+                }
+            }
+            _ => {
+                let reconnect_html = Render::reconnect(&boggle.lock().await.code);
+                let _ = ws_sender.send(Message::Text(reconnect_html));
+                None
+            }
+        }
+    }
+
+    // Spectators only need the session id, not a username
+    async fn handle_spectator_connection(
+        ws_sender: &UnboundedSender<Message>,
+        boggle: &Arc<Mutex<Boggle>>,
+        session: &Session,
+    ) -> Option<PlayerId> {
+        match session.get::<PlayerId>("id").await {
+            Ok(Some(spectator_id)) => {
+                let mut boggle = boggle.lock().await;
+                if boggle.watch(&spectator_id, ws_sender.clone()) {
+                    Some(spectator_id)
+                } else if boggle.is_solo() {
+                    let _ = ws_sender.send(Message::Text(Render::private_room()));
+                    None
                 } else {
-                    boggle.players.mark_active(&player_id);
+                    let _ = ws_sender.send(Message::Text(Render::removed(true)));
+                    None
                 }
-                Some(player_id)
             }
             _ => {
-                let reconnect_html = Render::reconnect();
+                let reconnect_html = Render::reconnect(&boggle.lock().await.code);
                 let _ = ws_sender.send(Message::Text(reconnect_html));
                 None
             }
@@ -152,22 +199,31 @@ impl WebSockets {
         let tx = boggle.lock().await.tx.clone();
         let mut rx = tx.subscribe();
 
-        while let Ok(msg) = rx.recv().await {
-            if ws_sender_clone.send(Message::Text(msg)).is_err() {
-                break;
+        // A client that falls behind skips what it missed; the next timer tick
+        // or scoreboard brings it up to date
+        loop {
+            match rx.recv().await {
+                Ok(msg) => {
+                    if ws_sender_clone.send(Message::Text(msg)).is_err() {
+                        break;
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
             }
         }
     }
 
-    async fn cleanup(boggle: &Arc<Mutex<Boggle>>, username: &PlayerId) {
+    async fn cleanup(rooms: Arc<Mutex<Rooms>>, boggle: &Arc<Mutex<Boggle>>, username: &PlayerId) {
         println!("Cleaning up player: {:?}", username);
         let mut boggle = boggle.lock().await;
 
-        boggle.players.mark_inactive(&username);
+        boggle.leave(username);
 
         if boggle.players.all_inactive() {
             boggle.players.remove_inactive();
             boggle.set_state_to_starting().await;
+            Rooms::schedule_removal(rooms, boggle.code.clone());
         }
     }
 }
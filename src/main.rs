@@ -2,19 +2,15 @@ use axum::{
     error_handling::HandleErrorLayer,
     http::StatusCode,
     routing::{get, post},
-    BoxError, Extension, Router,
+    BoxError, Router,
 };
-use std::{env, net::SocketAddr, sync::Arc};
+use std::{env, net::SocketAddr};
 use tower::{buffer::BufferLayer, limit::RateLimitLayer, ServiceBuilder};
 use tower_http::services::ServeDir;
 use tower_sessions::{Expiry, MemoryStore, SessionManagerLayer};
 
-mod handlers;
-mod models;
-mod render;
-
-use handlers::Handle;
-use models::Boggle;
+use boggle_game::handlers::Handle;
+use boggle_game::models::Rooms;
 
 #[tokio::main]
 async fn main() {
@@ -25,18 +21,37 @@ async fn main() {
         .with_secure(false)
         .with_expiry(Expiry::OnSessionEnd);
 
-    let boggle = Boggle::new();
+    let rooms = Rooms::new();
     let styles_path = env::var("STATIC_FILES_PATH").unwrap_or_else(|_| "/app/static".to_string());
     let app = Router::new()
         .route("/", get(Handle::root))
         .route("/username", post(Handle::username))
-        .route("/submit_word", post(Handle::submit_word))
-        .route("/new_game", post(Handle::new_game))
-        .route("/get_score", post(Handle::get_player_score))
-        .layer(Extension(Arc::clone(&boggle)))
+        .route("/rooms", post(Handle::create_room))
+        .route("/solo", post(Handle::create_solo_room))
+        .route("/daily", post(Handle::create_daily_room))
+        .route("/join", post(Handle::join_room))
+        .route(
+            "/challenge/:id",
+            get(Handle::challenge).post(Handle::accept_challenge),
+        )
+        .route("/room/:code", get(Handle::room))
+        .route("/room/:code/submit_word", post(Handle::submit_word))
+        .route("/room/:code/new_game", post(Handle::new_game))
+        .route("/room/:code/settings", post(Handle::update_settings))
+        .route("/room/:code/ready", post(Handle::toggle_ready))
+        .route("/room/:code/team", post(Handle::switch_team))
+        .route("/room/:code/bots", post(Handle::add_bot))
+        .route("/room/:code/kick", post(Handle::kick))
+        .route("/room/:code/host", post(Handle::transfer_host))
+        .route("/room/:code/pause", post(Handle::pause))
+        .route("/room/:code/resume", post(Handle::resume))
+        .route("/room/:code/end", post(Handle::end_round))
+        .route("/room/:code/hint", post(Handle::request_hint))
+        .route("/room/:code/challenge", post(Handle::create_challenge))
+        .route("/room/:code/get_score", post(Handle::get_player_score))
         .nest_service("/static", ServeDir::new(styles_path))
-        .route("/ws", get(Handle::websocket))
-        .with_state(boggle)
+        .route("/room/:code/ws", get(Handle::websocket))
+        .with_state(rooms)
         .layer(session_layer)
         .layer(
             ServiceBuilder::new()
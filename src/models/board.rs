@@ -1,149 +1,563 @@
-use crate::models::{Dictionary, SearchResult, WordList};
+use crate::models::{BoardPolicy, Dictionary, Scoring, Solver, Tile, WordList};
 use rand::seq::{IteratorRandom, SliceRandom};
-use std::sync::Arc;
+use rand::{Rng, SeedableRng};
+// Seeds are shared and replayed, so the generator has to roll the same board on
+// every platform and version, which StdRng doesn't promise
+use super::form_value::FormValue;
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use std::{fmt, sync::Arc};
 
-// Define the size of the Boggle board
-const SIZE: usize = 4;
-
-// Boggle dice configuration
-const DICE: [&str; 16] = [
+// Classic Boggle dice configuration (4x4)
+// A lowercase letter belongs to the face before it, so "Qu" is a single face
+const CLASSIC_DICE: [&str; 16] = [
     "AAEEGN", "ELRTTY", "AOOTTW", "ABBJOO", "EHRTVW", "CIMOTU", "DISTTY", "EIOSST", "DELRVY",
-    "ACHOPS", "HIMNQU", "EEINSU", "EEGHNW", "AFFKPS", "HLNNRZ", "DEILRX",
+    "ACHOPS", "HIMNQuU", "EEINSU", "EEGHNW", "AFFKPS", "HLNNRZ", "DEILRX",
+];
+
+// Original 1976 Boggle dice configuration (4x4)
+const ORIGINAL_DICE: [&str; 16] = [
+    "AACIOT", "ABILTY", "ABJMOQu", "ACDEMP", "ACELRS", "ADENVZ", "AHMORS", "BIFORX", "DENOSW",
+    "DKNOTU", "EEFHIY", "EGKLUY", "EGINTV", "EHINPS", "ELPSTU", "GILRUW",
+];
+
+// Big Boggle dice configuration (5x5)
+const BIG_DICE: [&str; 25] = [
+    "AAAFRS", "AAEEEE", "AAFIRS", "ADENNN", "AEEEEM", "AEEGMU", "AEGMNN", "AFIRSY", "BJKQuXZ",
+    "CCENST", "CEIILT", "CEILPT", "CEIPST", "DDHNOT", "DHHLOR", "DHLNOR", "DHLNOR", "EIIITT",
+    "EMOTTT", "ENSSSU", "FIPRSY", "GORRVW", "IPRRRY", "NOOTUW", "OOOTTU",
 ];
 
+// Super Big Boggle dice configuration (6x6)
+// The three blank faces of "EIO###" are rerolled, so they are left out.
+const SUPER_BIG_DICE: [&str; 36] = [
+    "AAAFRS",
+    "AAEEEE",
+    "AAEEOO",
+    "AAFIRS",
+    "ABDEIO",
+    "ADENNN",
+    "AEEEEM",
+    "AEEGMU",
+    "AEGMNN",
+    "AEILMN",
+    "AEINOU",
+    "AFIRSY",
+    "AnErHeInQuTh",
+    "BBJKXZ",
+    "CCENST",
+    "CDDLNN",
+    "CEIITT",
+    "CEIPST",
+    "CFGNUY",
+    "DDHNOT",
+    "DHHLOR",
+    "DHHNOW",
+    "DHLNOR",
+    "EHILRS",
+    "EIILST",
+    "EILPST",
+    "EIOEIO",
+    "EMTTTO",
+    "ENSSSU",
+    "GORRVW",
+    "HIRSTV",
+    "HOPRST",
+    "IPRSYY",
+    "JKQuWXZ",
+    "NOOTUW",
+    "OOOTTU",
+];
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BoardSize {
+    #[default]
+    Classic,
+    Big,
+    SuperBig,
+}
+
+impl BoardSize {
+    pub const ALL: [BoardSize; 3] = [BoardSize::Classic, BoardSize::Big, BoardSize::SuperBig];
+
+    // Number of rows and columns on the board
+    pub fn dimension(&self) -> usize {
+        match self {
+            BoardSize::Classic => 4,
+            BoardSize::Big => 5,
+            BoardSize::SuperBig => 6,
+        }
+    }
+
+    pub fn default_dice(&self) -> DiceSet {
+        match self {
+            BoardSize::Classic => DiceSet::Classic,
+            BoardSize::Big => DiceSet::Big,
+            BoardSize::SuperBig => DiceSet::SuperBig,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            BoardSize::Classic => "4x4",
+            BoardSize::Big => "5x5",
+            BoardSize::SuperBig => "6x6",
+        }
+    }
+
+    pub fn from_dimension(dimension: usize) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|size| size.dimension() == dimension)
+    }
+}
+
+impl FormValue for BoardSize {}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiceSet {
+    #[default]
+    Classic,
+    Original,
+    Big,
+    SuperBig,
+}
+
+impl DiceSet {
+    pub const ALL: [DiceSet; 4] = [
+        DiceSet::Classic,
+        DiceSet::Original,
+        DiceSet::Big,
+        DiceSet::SuperBig,
+    ];
+
+    pub fn size(&self) -> BoardSize {
+        match self {
+            DiceSet::Classic | DiceSet::Original => BoardSize::Classic,
+            DiceSet::Big => BoardSize::Big,
+            DiceSet::SuperBig => BoardSize::SuperBig,
+        }
+    }
+
+    pub fn dice(&self) -> &'static [&'static str] {
+        match self {
+            DiceSet::Classic => &CLASSIC_DICE,
+            DiceSet::Original => &ORIGINAL_DICE,
+            DiceSet::Big => &BIG_DICE,
+            DiceSet::SuperBig => &SUPER_BIG_DICE,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            DiceSet::Classic => "Classic",
+            DiceSet::Original => "Original (1976)",
+            DiceSet::Big => "Big Boggle",
+            DiceSet::SuperBig => "Super Big Boggle",
+        }
+    }
+
+    // Dice sets that fill a board of the given size
+    pub fn for_size(size: BoardSize) -> impl Iterator<Item = DiceSet> {
+        Self::ALL
+            .into_iter()
+            .filter(move |dice| dice.size() == size)
+    }
+}
+
+impl FormValue for DiceSet {}
+
+// How the board for a new game should be created
+#[derive(Debug, Clone)]
+pub enum BoardSetup {
+    Roll {
+        dice: DiceSet,
+        seed: Option<u64>,
+        policy: BoardPolicy,
+    },
+    Notation(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BoardNotationError {
+    Empty,
+    UnsupportedSize(usize),
+    NotSquare,
+    InvalidCharacter(char),
+}
+
+impl fmt::Display for BoardNotationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BoardNotationError::Empty => write!(f, "Board notation is empty"),
+            BoardNotationError::UnsupportedSize(rows) => {
+                write!(f, "Boards must have 4, 5 or 6 rows, found {}", rows)
+            }
+            BoardNotationError::NotSquare => {
+                write!(f, "Every row must have as many tiles as there are rows")
+            }
+            BoardNotationError::InvalidCharacter(ch) => {
+                write!(f, "Invalid character in board notation: {:?}", ch)
+            }
+        }
+    }
+}
+
+impl std::error::Error for BoardNotationError {}
+
+// Why a new game's board couldn't be set up
+#[derive(Debug, Clone, PartialEq)]
+pub enum BoardSetupError {
+    Notation(BoardNotationError),
+    // Holds the number of boards rolled before giving up
+    PolicyNotMet(usize),
+}
+
+impl fmt::Display for BoardSetupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BoardSetupError::Notation(e) => write!(f, "{}", e),
+            BoardSetupError::PolicyNotMet(attempts) => write!(
+                f,
+                "None of {} boards met the board limits, try looser ones",
+                attempts
+            ),
+        }
+    }
+}
+
+impl std::error::Error for BoardSetupError {}
+
+impl From<BoardNotationError> for BoardSetupError {
+    fn from(e: BoardNotationError) -> Self {
+        BoardSetupError::Notation(e)
+    }
+}
+
 #[derive(Debug)]
 pub struct Board {
-    pub board: Vec<Vec<char>>,
+    pub board: Vec<Vec<Tile>>,
+    pub size: BoardSize,
+    // Boards imported from notation were not rolled, so they have no seed or dice set
+    pub seed: Option<u64>,
+    pub dice: Option<DiceSet>,
     dictionary: Arc<Dictionary>,
     pub words: WordList,
 }
 
 impl Board {
-    // Generate a new Boggle board
-    pub fn new(dictionary: &Arc<Dictionary>) -> Self {
-        let mut rng = rand::thread_rng();
-        let mut dice = DICE;
+    // Words need at least this many letters unless a game asks for more
+    pub const MIN_WORD_LENGTH: usize = 3;
+    pub const MAX_WORD_LENGTH: usize = 16;
+
+    // Stands in until the first round, so a new room doesn't solve a board nobody plays
+    pub fn empty(dictionary: &Arc<Dictionary>) -> Self {
+        Board {
+            board: Vec::new(),
+            size: BoardSize::default(),
+            seed: None,
+            dice: None,
+            dictionary: dictionary.clone(),
+            words: WordList::new(),
+        }
+    }
+
+    // Generate a new Boggle board, the same seed and dice set always roll the same board
+    pub fn new(dictionary: &Arc<Dictionary>, dice_set: DiceSet, seed: u64) -> Self {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let size = dice_set.size();
+        let mut dice = dice_set.dice().to_vec();
         dice.shuffle(&mut rng);
 
-        let board_chars: Vec<char> = dice
+        let board_tiles: Vec<Tile> = dice
             .iter()
-            .map(|&die| die.chars().choose(&mut rng).unwrap())
+            .map(|&die| Tile::faces(die).into_iter().choose(&mut rng).unwrap())
             .collect();
 
-        let board: Vec<Vec<char>> = board_chars
-            .chunks(SIZE)
+        let board: Vec<Vec<Tile>> = board_tiles
+            .chunks(size.dimension())
             .map(|chunk| chunk.to_vec())
             .collect();
 
+        let mut board = Self::from_tiles(dictionary, board, size, Some(seed));
+        board.dice = Some(dice_set);
+        board
+    }
+
+    // Roll boards until one is accepted by the policy, scored the way the room scores
+    // Each board keeps the seed it was rolled from, so replaying it skips the rerolls.
+    // Solving hundreds of boards takes a while, so rooms run this off the async runtime.
+    pub fn generate(
+        dictionary: &Arc<Dictionary>,
+        dice: DiceSet,
+        seed: Option<u64>,
+        policy: &BoardPolicy,
+        min_word_length: usize,
+        scoring: &Scoring,
+    ) -> Result<Self, BoardSetupError> {
+        let seed = seed.unwrap_or_else(Self::random_seed);
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let mut board =
+            Self::new(dictionary, dice, seed).with_min_word_length(min_word_length, scoring);
+
+        for _ in 1..BoardPolicy::MAX_ATTEMPTS {
+            if policy.accepts(&board) {
+                return Ok(board);
+            }
+            board = Self::new(dictionary, dice, rng.gen())
+                .with_min_word_length(min_word_length, scoring);
+        }
+
+        if policy.accepts(&board) {
+            Ok(board)
+        } else {
+            Err(BoardSetupError::PolicyNotMet(BoardPolicy::MAX_ATTEMPTS))
+        }
+    }
+
+    // Build a board from rows of tiles separated by '/', e.g. "ABCD/EFGH/IJKL/MNQuP"
+    // A lowercase letter belongs to the tile before it, like the faces of a die.
+    // Notation written entirely in lowercase is read as single-letter tiles.
+    pub fn from_notation(
+        dictionary: &Arc<Dictionary>,
+        notation: &str,
+    ) -> Result<Self, BoardNotationError> {
+        let notation = notation.trim();
+        if notation.is_empty() {
+            return Err(BoardNotationError::Empty);
+        }
+
+        let notation = if notation.chars().any(|c| c.is_uppercase()) {
+            notation.to_string()
+        } else {
+            notation.to_uppercase()
+        };
+
+        if let Some(ch) = notation
+            .chars()
+            .find(|&c| c != '/' && !c.is_ascii_alphabetic())
+        {
+            return Err(BoardNotationError::InvalidCharacter(ch));
+        }
+
+        // A row can't start by continuing a tile
+        if let Some(ch) = notation
+            .split('/')
+            .filter_map(|row| row.chars().next())
+            .find(|c| c.is_lowercase())
+        {
+            return Err(BoardNotationError::InvalidCharacter(ch));
+        }
+
+        let board: Vec<Vec<Tile>> = notation.split('/').map(Tile::faces).collect();
+
+        let size = BoardSize::from_dimension(board.len())
+            .ok_or(BoardNotationError::UnsupportedSize(board.len()))?;
+
+        if board.iter().any(|row| row.len() != board.len()) {
+            return Err(BoardNotationError::NotSquare);
+        }
+
+        Ok(Self::from_tiles(dictionary, board, size, None))
+    }
+
+    pub fn setup(
+        dictionary: &Arc<Dictionary>,
+        setup: &BoardSetup,
+        min_word_length: usize,
+        scoring: &Scoring,
+    ) -> Result<Self, BoardSetupError> {
+        match setup {
+            BoardSetup::Roll { dice, seed, policy } => {
+                Self::generate(dictionary, *dice, *seed, policy, min_word_length, scoring)
+            }
+            BoardSetup::Notation(notation) => Ok(Self::from_notation(dictionary, notation)?
+                .with_min_word_length(min_word_length, scoring)),
+        }
+    }
+
+    // Drop the solved words that are too short to count, and score the rest
+    pub fn with_min_word_length(mut self, min_word_length: usize, scoring: &Scoring) -> Self {
+        self.words.remove_shorter_than(min_word_length);
+        self.words.total_words(scoring);
+        self
+    }
+
+    fn from_tiles(
+        dictionary: &Arc<Dictionary>,
+        board: Vec<Vec<Tile>>,
+        size: BoardSize,
+        seed: Option<u64>,
+    ) -> Self {
         let mut boggle_board = Board {
             board,
+            size,
+            seed,
+            dice: None,
             dictionary: dictionary.clone(),
             words: WordList::new(),
         };
 
         boggle_board.find_valid_words();
-        boggle_board.words.total_words();
+        // Scored with the classic rule until a room rescores it with its own
+        boggle_board.words.total_words(&Scoring::default());
 
         boggle_board
     }
 
+    // Write the board in the notation read by `from_notation`
+    pub fn notation(&self) -> String {
+        self.board
+            .iter()
+            .map(|row| row.iter().map(|tile| tile.to_string()).collect::<String>())
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    pub fn random_seed() -> u64 {
+        rand::thread_rng().gen()
+    }
+
     pub fn find_valid_words(&mut self) {
-        let mut visited = vec![vec![false; SIZE]; SIZE];
-        let mut current_word = String::new();
+        self.words = Solver::new(&self.dictionary, &self.board).solve();
+    }
 
-        for i in 0..SIZE {
-            for j in 0..SIZE {
-                self.dfs(i, j, &mut visited, &mut current_word);
-            }
-        }
+    // A single word of letters that could fit on a board
+    pub fn is_well_formed(word: &str) -> bool {
+        !(word.contains(' ')
+            || word.chars().any(|c| !c.is_alphabetic())
+            || word.len() > Self::MAX_WORD_LENGTH)
     }
+}
 
-    fn dfs(&mut self, i: usize, j: usize, visited: &mut Vec<Vec<bool>>, current_word: &mut String) {
-        if i >= SIZE || j >= SIZE || visited[i][j] {
-            return;
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::dictionary::TrieNode;
+    use crate::models::Dictionaries;
 
-        visited[i][j] = true;
-        let ch = self.board[i][j];
-        current_word.push(ch);
+    fn empty_dictionary() -> Arc<Dictionary> {
+        Arc::new(Dictionary {
+            root: TrieNode::default(),
+        })
+    }
 
-        // Check for 'Q' and handle as both 'Q' and 'QU'
-        if ch == 'Q' {
-            self.check_for_word(i, j, visited, current_word); // Check for 'Q'
-            current_word.push('U');
-            self.check_for_word(i, j, visited, current_word); // Check for 'QU'
-            current_word.pop(); // Remove 'U' after checking
-        } else {
-            self.check_for_word(i, j, visited, current_word); // Normal processing
-        }
-
-        // Backtrack
-        current_word.pop();
-        visited[i][j] = false;
-    }
-
-    fn check_for_word(
-        &mut self,
-        i: usize,
-        j: usize,
-        visited: &mut Vec<Vec<bool>>,
-        current_word: &mut String,
-    ) {
-        match self.dictionary.search(&current_word.to_lowercase()) {
-            SearchResult::ValidWord(definition) => {
-                let word = current_word.clone();
-                if !self.words.contains(&word) {
-                    self.words.add(&word, definition)
-                }
-                // Continue search even after finding a valid word
-                self.continue_search(i, j, visited, current_word);
-            }
-            SearchResult::ValidPrefix => {
-                // Continue search for a valid prefix
-                self.continue_search(i, j, visited, current_word);
-            }
-            SearchResult::NotFound => {
-                // Stop search if not found
-                return;
-            }
-        }
+    #[test]
+    fn seed_rolls_the_same_board() {
+        let dictionary = empty_dictionary();
+        let first = Board::new(&dictionary, DiceSet::Classic, 42);
+        let second = Board::new(&dictionary, DiceSet::Classic, 42);
+        assert_eq!(first.notation(), second.notation());
+        assert_eq!(first.seed, Some(42));
     }
 
-    fn continue_search(
-        &mut self,
-        i: usize,
-        j: usize,
-        visited: &mut Vec<Vec<bool>>,
-        current_word: &mut String,
-    ) {
-        // Define the neighbor offsets
-        let row_offsets = [-1, -1, -1, 0, 0, 1, 1, 1];
-        let col_offsets = [-1, 0, 1, -1, 1, -1, 0, 1];
-
-        // Iterate over all possible neighbors
-        for k in 0..8 {
-            let new_i = i as isize + row_offsets[k];
-            let new_j = j as isize + col_offsets[k];
-            if new_i >= 0 && new_i < SIZE as isize && new_j >= 0 && new_j < SIZE as isize {
-                self.dfs(new_i as usize, new_j as usize, visited, current_word);
-            }
+    // Changing this layout breaks every seed players have shared
+    #[test]
+    fn seed_layout_is_pinned() {
+        let dictionary = empty_dictionary();
+        let board = Board::new(&dictionary, DiceSet::Classic, 42);
+        assert_eq!(board.notation(), "NOOT/NEKD/LILH/CNLT");
+    }
+
+    #[test]
+    fn notation_round_trips() {
+        let dictionary = empty_dictionary();
+        for notation in ["ABCD/EFGH/IJKL/MNQuP", "ABCDE/FGHIJ/KLMNO/PQuRST/THeVWX"] {
+            let board = Board::from_notation(&dictionary, notation).unwrap();
+            assert_eq!(board.notation(), notation);
+            assert_eq!(board.seed, None);
         }
     }
 
-    pub fn calculate_score(word_length: usize) -> u32 {
-        match word_length {
-            3 | 4 => 1,
-            5 => 2,
-            6 => 3,
-            7 => 5,
-            _ => 11,
+    #[test]
+    fn lowercase_notation_is_single_letters() {
+        let board = Board::from_notation(&empty_dictionary(), "abcd/efgh/ijkl/mnqp").unwrap();
+        assert_eq!(board.notation(), "ABCD/EFGH/IJKL/MNQP");
+        assert_eq!(board.size, BoardSize::Classic);
+    }
+
+    #[test]
+    fn rolled_boards_round_trip() {
+        let dictionary = empty_dictionary();
+        for dice in DiceSet::ALL {
+            let board = Board::new(&dictionary, dice, 7);
+            let copy = Board::from_notation(&dictionary, &board.notation()).unwrap();
+            assert_eq!(copy.board, board.board);
+            assert_eq!(copy.size, dice.size());
         }
     }
 
-    pub fn is_valid_word(word: &str) -> bool {
-        !(word.contains(' ')
-            || word.chars().any(|c| !c.is_alphabetic())
-            || word.len() <= 2
-            || word.len() > 16)
+    #[test]
+    fn bad_notation_is_rejected() {
+        let dictionary = empty_dictionary();
+        let error = |notation| Board::from_notation(&dictionary, notation).unwrap_err();
+        assert_eq!(error("  "), BoardNotationError::Empty);
+        assert_eq!(error("ABC/DEF/GHI"), BoardNotationError::UnsupportedSize(3));
+        assert_eq!(error("ABCD/EFGH/IJKL/MNO"), BoardNotationError::NotSquare);
+        assert_eq!(
+            error("ABCD/EFGH/IJ1L/MNOP"),
+            BoardNotationError::InvalidCharacter('1')
+        );
+        assert_eq!(
+            error("ABCD/uEFG/IJKL/MNOP"),
+            BoardNotationError::InvalidCharacter('u')
+        );
+    }
+
+    #[test]
+    fn boards_that_never_meet_the_policy_give_up() {
+        let policy = BoardPolicy {
+            min_words: Some(1),
+            ..BoardPolicy::default()
+        };
+        let error = Board::generate(
+            &empty_dictionary(),
+            DiceSet::Classic,
+            Some(42),
+            &policy,
+            Board::MIN_WORD_LENGTH,
+            &Scoring::Classic,
+        )
+        .unwrap_err();
+        assert_eq!(
+            error,
+            BoardSetupError::PolicyNotMet(BoardPolicy::MAX_ATTEMPTS)
+        );
+    }
+
+    #[test]
+    fn rerolled_boards_replay_from_their_own_seed() {
+        let dictionaries = Dictionaries::from_words(&["ten", "net", "tone", "note", "one"]);
+        let dictionary = dictionaries.get(Dictionaries::DEFAULT);
+        let policy = BoardPolicy {
+            min_words: Some(2),
+            ..BoardPolicy::default()
+        };
+        let board = Board::generate(
+            &dictionary,
+            DiceSet::Classic,
+            Some(42),
+            &policy,
+            Board::MIN_WORD_LENGTH,
+            &Scoring::Classic,
+        )
+        .unwrap();
+        assert!(policy.accepts(&board));
+
+        // Seed 42's own board falls short, so this one came from a reroll
+        let seed = board.seed.unwrap();
+        assert_ne!(seed, 42);
+        let replay = Board::new(&dictionary, DiceSet::Classic, seed);
+        assert_eq!(replay.notation(), board.notation());
+    }
+
+    #[test]
+    fn empty_board_has_no_words() {
+        let board = Board::empty(&empty_dictionary());
+        assert!(board.board.is_empty());
+        assert!(board.words.is_empty());
+        assert_eq!(board.notation(), "");
     }
 }
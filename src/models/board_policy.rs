@@ -0,0 +1,106 @@
+use super::board::Board;
+
+// Limits a rolled board has to meet before it is played, `None` means no limit
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BoardPolicy {
+    pub min_words: Option<usize>,
+    pub max_words: Option<usize>,
+    pub min_score: Option<u32>,
+    // Require at least one word with this many letters
+    pub long_word: Option<usize>,
+}
+
+impl BoardPolicy {
+    // Give up rerolling after this many boards
+    pub const MAX_ATTEMPTS: usize = 500;
+
+    // No board can have fewer words than the minimum and more than the maximum
+    pub fn is_valid(&self) -> bool {
+        match (self.min_words, self.max_words) {
+            (Some(min), Some(max)) => min <= max,
+            _ => true,
+        }
+    }
+
+    pub fn accepts(&self, board: &Board) -> bool {
+        let word_count = board.words.len();
+
+        self.min_words.is_none_or(|min| word_count >= min)
+            && self.max_words.is_none_or(|max| word_count <= max)
+            && self
+                .min_score
+                .is_none_or(|min| board.words.total_score >= min)
+            && self
+                .long_word
+                .is_none_or(|length| board.words.longest_word() >= length)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Dictionaries;
+
+    // CAT and ACT, scoring a point each
+    fn board() -> Board {
+        let dictionaries = Dictionaries::from_words(&["cat", "act", "tact"]);
+        let dictionary = dictionaries.get(Dictionaries::DEFAULT);
+        Board::from_notation(&dictionary, "CAXX/TXXX/XXXX/XXXX").unwrap()
+    }
+
+    #[test]
+    fn no_limits_accept_any_board() {
+        assert!(BoardPolicy::default().accepts(&board()));
+    }
+
+    #[test]
+    fn each_limit_is_checked() {
+        let board = board();
+        let accepts = |policy: BoardPolicy| policy.accepts(&board);
+        let policy = BoardPolicy::default();
+
+        assert!(accepts(BoardPolicy {
+            min_words: Some(2),
+            ..policy
+        }));
+        assert!(!accepts(BoardPolicy {
+            min_words: Some(3),
+            ..policy
+        }));
+        assert!(accepts(BoardPolicy {
+            max_words: Some(2),
+            ..policy
+        }));
+        assert!(!accepts(BoardPolicy {
+            max_words: Some(1),
+            ..policy
+        }));
+        assert!(accepts(BoardPolicy {
+            min_score: Some(2),
+            ..policy
+        }));
+        assert!(!accepts(BoardPolicy {
+            min_score: Some(3),
+            ..policy
+        }));
+        assert!(accepts(BoardPolicy {
+            long_word: Some(3),
+            ..policy
+        }));
+        assert!(!accepts(BoardPolicy {
+            long_word: Some(4),
+            ..policy
+        }));
+    }
+
+    #[test]
+    fn word_limits_must_leave_room_for_a_board() {
+        let policy = |min, max| BoardPolicy {
+            min_words: Some(min),
+            max_words: Some(max),
+            ..BoardPolicy::default()
+        };
+        assert!(policy(5, 5).is_valid());
+        assert!(!policy(6, 5).is_valid());
+    }
+}
@@ -1,166 +1,907 @@
-use crate::models::{Board, Dictionary, PlayerId, PlayerList, Timer};
+use crate::models::{
+    Board, BoardSetup, BoardSetupError, Challenge, ChallengeAttempt, ChallengeId, ChallengeResult,
+    Daily, Dictionaries, Dictionary, Difficulty, GameSettings, Hint, HintKind, PlayerId,
+    PlayerList, RoomCode, Scoring, ScoringMode, ScoringRule, SearchResult, SettingsError,
+    SubmissionResult, Timer, TimerEvent, WordList,
+};
 use crate::render::Render;
 
+use axum::extract::ws::Message;
 use maud::html;
-use std::{env, sync::Arc};
-use tokio::sync::{broadcast, Mutex};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, mpsc::UnboundedSender, Mutex};
 
 // Define possible game states
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum BoggleStateEnum {
     Starting,
+    // Counting down to a round whose board is already rolled
+    Countdown,
     InProgress,
+    // The timer is stopped and the board hidden until the host resumes
+    Paused,
     GameOver,
 }
 
+// What a new game needs to roll its board, taken from the room so the board
+// can be built without holding the room's lock
+#[derive(Debug)]
+struct GameSetup {
+    dictionary: Arc<Dictionary>,
+    setup: BoardSetup,
+    min_word_length: usize,
+    scoring: Scoring,
+}
+
 #[derive(Debug)]
 pub struct Boggle {
+    pub code: RoomCode,
     pub players: PlayerList,
+    // Starts games and changes settings; passed on when the host leaves
+    host: Option<PlayerId>,
+    banned: HashSet<PlayerId>,
+    // Solo rooms are kept to the player who made them
+    solo_player: Option<PlayerId>,
+    // Set when the solo room plays the day's shared board
+    daily: Option<Daily>,
+    // Set when the solo room plays a board someone else sent
+    challenge: Option<ChallengeAttempt>,
+    // Challenges sent from the last round, so asking again gives the same link
+    challenges_sent: HashMap<PlayerId, ChallengeId>,
+    // Watch the room without playing, so they're kept out of the player list
+    spectators: HashMap<PlayerId, UnboundedSender<Message>>,
     state: BoggleStateEnum,
+    // Round of the current match, 0 before the first one starts
+    round: u32,
+    // Counts every game so bots from an earlier one know to stop
+    game: u64,
     board: Board,
-    dictionary: Arc<Dictionary>,
+    pub settings: GameSettings,
+    dictionaries: Arc<Dictionaries>,
     timer: Arc<Mutex<Timer>>,
     pub tx: broadcast::Sender<String>,
-    boggle_channel_tx: broadcast::Sender<BoggleStateEnum>,
+    created_at: Instant,
 }
 
 impl Boggle {
-    pub const GAME_DURATION: u32 = 180;
-
-    pub fn new() -> Arc<Mutex<Self>> {
-        let styles_path =
-            env::var("STATIC_FILES_PATH").unwrap_or_else(|_| "/app/static".to_string());
-        let file_path = format!("{}/scrabble-dictionary.txt", styles_path);
-        let dictionary =
-            Arc::new(Dictionary::new(&file_path).expect("Failed to create dictionary"));
-
-        let (tx, _) = broadcast::channel(10);
-        let (boggle_channel_tx, _) = broadcast::channel(1);
-        let timer = Timer::new(tx.clone(), boggle_channel_tx.clone());
+    pub const COUNTDOWN_SECONDS: u32 = 3;
+    pub const MAX_BOTS: usize = 4;
+    // Messages a slow client can fall behind by on the room broadcast
+    const BROADCAST_CAPACITY: usize = 64;
+
+    pub fn new(
+        code: RoomCode,
+        dictionaries: Arc<Dictionaries>,
+        solo_player: Option<PlayerId>,
+        daily: Option<Daily>,
+        challenge: Option<ChallengeAttempt>,
+    ) -> Arc<Mutex<Self>> {
+        // A challenge is played with the settings it was sent with
+        let settings = challenge
+            .as_ref()
+            .and_then(|attempt| attempt.challenge())
+            .map(|challenge| challenge.settings().normalize(&dictionaries))
+            .unwrap_or_default();
+
+        let (tx, _) = broadcast::channel(Self::BROADCAST_CAPACITY);
+        let (boggle_channel_tx, boggle_rx) = broadcast::channel(1);
+        let timer = Timer::new(tx.clone(), boggle_channel_tx);
         let boggle = Arc::new(Mutex::new(Self {
+            code,
             players: PlayerList::new(),
-            board: Board::new(&dictionary),
-            dictionary,
-            boggle_channel_tx,
+            host: None,
+            banned: HashSet::new(),
+            solo_player,
+            daily,
+            challenge,
+            challenges_sent: HashMap::new(),
+            spectators: HashMap::new(),
+            board: Board::empty(&dictionaries.get(Dictionaries::DEFAULT)),
+            settings,
+            dictionaries,
             state: BoggleStateEnum::Starting,
+            round: 0,
+            game: 0,
             timer,
             tx,
+            created_at: Instant::now(),
         }));
 
-        let boggle_clone = Arc::clone(&boggle);
+        // The loop only holds a weak reference so the room can be dropped
+        let boggle_weak = Arc::downgrade(&boggle);
 
         tokio::spawn(async move {
-            Boggle::start_game_loop(boggle_clone).await;
+            Boggle::start_game_loop(boggle_weak, boggle_rx).await;
         });
 
         boggle
     }
 
+    // Spectators get the same views as players, without anything to play with
     pub async fn get_game_state(&self, player_id: &PlayerId) -> String {
-        let player = self.players.get(player_id);
-        let found_words = match player {
-            Some(p) => &p.words,
-            None => return "Player not found".to_string(),
-        };
+        let found_words = self.found_words(player_id);
+        if found_words.is_none() && !self.spectators.contains_key(player_id) {
+            return "Player not found".to_string();
+        }
 
         match self.state {
-            BoggleStateEnum::Starting => Render::starting_state(),
+            BoggleStateEnum::Starting => Render::starting_state(&self.lobby_panel(player_id)),
+            BoggleStateEnum::Countdown => Render::countdown_state("Get ready"),
             BoggleStateEnum::InProgress => {
-                let fmt_timer = Timer::format_time(Boggle::GAME_DURATION);
-                Render::inprogress_state(&fmt_timer, &self.board, Some(found_words))
+                let fmt_timer = self.format_remaining(self.timer.lock().await.remaining());
+                self.round_view(player_id, &fmt_timer)
+            }
+            BoggleStateEnum::Paused => {
+                let fmt_timer = self.format_remaining(self.timer.lock().await.remaining());
+                Render::paused_state(&self.code, &fmt_timer, self.is_host(player_id))
             }
-            BoggleStateEnum::GameOver => Render::gameover_state(&self.board, &self.players),
+            BoggleStateEnum::GameOver => self.gameover_state(player_id),
         }
     }
 
-    pub async fn new_game(&mut self) {
-        match self.state {
-            BoggleStateEnum::InProgress => (),
-            _ => {
-                self.players.remove_inactive();
-                self.players.clear_state();
-                self.timer.lock().await.start().await;
+    // Daily and challenge rooms have nothing to set up, just the results
+    fn lobby_panel(&self, viewer: &PlayerId) -> String {
+        if let Some(daily) = &self.daily {
+            return Render::daily_panel(
+                &self.code,
+                &daily.day.date(),
+                &daily.leaderboard(),
+                daily.has_played(viewer),
+            );
+        }
+
+        match self
+            .challenge
+            .as_ref()
+            .and_then(|attempt| attempt.challenge())
+        {
+            Some(challenge) => Render::challenge_panel(&self.code, &challenge, viewer),
+            None => Render::lobby_panel(
+                &self.code,
+                &self.settings,
+                &self.dictionaries,
+                &self.players,
+                self.host.as_ref(),
+                viewer,
+                self.is_solo(),
+            ),
+        }
+    }
+
+    // Adds a new player or reconnects a returning one; banned players are turned away
+    pub fn join(
+        &mut self,
+        player_id: &PlayerId,
+        sender: UnboundedSender<Message>,
+        username: PlayerId,
+    ) -> bool {
+        if self.banned.contains(player_id) || !self.admits(player_id) {
+            return false;
+        }
+
+        if self.players.contains_key(player_id) {
+            self.players.reconnect(player_id, sender);
+        } else {
+            self.players.add_player(player_id.clone(), sender, username);
+            self.players.assign_team(player_id, self.settings.teams);
+        }
+
+        // The first player in, or the first back after the host left, hosts the room
+        if !self
+            .host
+            .as_ref()
+            .is_some_and(|host| self.players.is_active(host))
+        {
+            self.host = Some(player_id.clone());
+        }
+
+        self.broadcast_presence();
+        true
+    }
+
+    pub fn is_solo(&self) -> bool {
+        self.solo_player.is_some()
+    }
+
+    fn admits(&self, player_id: &PlayerId) -> bool {
+        self.solo_player
+            .as_ref()
+            .is_none_or(|solo_player| solo_player == player_id)
+    }
+
+    fn daily_player(&self) -> Option<(&Daily, &PlayerId)> {
+        Some((self.daily.as_ref()?, self.solo_player.as_ref()?))
+    }
+
+    fn challenge_player(&self) -> Option<(&ChallengeAttempt, &PlayerId)> {
+        Some((self.challenge.as_ref()?, self.solo_player.as_ref()?))
+    }
+
+    // Daily and challenge boards are played with the settings everyone else had
+    fn fixed_board(&self) -> bool {
+        self.daily.is_some() || self.challenge.is_some()
+    }
+
+    // Nobody watches a solo room, and banned players can't come back to watch
+    pub fn watch(&mut self, spectator_id: &PlayerId, sender: UnboundedSender<Message>) -> bool {
+        if self.is_solo() || self.banned.contains(spectator_id) {
+            return false;
+        }
+
+        self.spectators.insert(spectator_id.clone(), sender);
+        true
+    }
+
+    pub fn stop_watching(&mut self, spectator_id: &PlayerId) {
+        self.spectators.remove(spectator_id);
+    }
+
+    pub fn leave(&mut self, player_id: &PlayerId) {
+        self.players.mark_inactive(player_id);
+
+        if self.is_host(player_id) {
+            self.host = self.next_host();
+        }
+        self.broadcast_presence();
+    }
 
-                self.state = BoggleStateEnum::InProgress;
-                self.board = Board::new(&self.dictionary);
+    fn next_host(&self) -> Option<PlayerId> {
+        self.players
+            .get_active_players_sorted_by_name()
+            .into_iter()
+            .find(|(_, player)| !player.is_bot())
+            .map(|(player_id, _)| player_id.clone())
+    }
+
+    pub fn is_host(&self, player_id: &PlayerId) -> bool {
+        self.host.as_ref() == Some(player_id)
+    }
+
+    pub fn transfer_host(&mut self, player_id: &PlayerId) -> bool {
+        if !self.players.is_human(player_id) {
+            return false;
+        }
+
+        self.host = Some(player_id.clone());
+        self.broadcast_presence();
+        true
+    }
+
+    // Removes the player from the room, and keeps them out when banned
+    pub fn kick(&mut self, player_id: &PlayerId, ban: bool) -> bool {
+        if self.is_host(player_id) {
+            return false;
+        }
+
+        let Some(player) = self.players.remove(player_id) else {
+            return false;
+        };
+        if ban {
+            self.banned.insert(player_id.clone());
+        }
 
-                let fmt_timer = Timer::format_time(Boggle::GAME_DURATION);
+        player.send(Render::removed(ban));
+        let _ = player.sender.send(Message::Close(None));
 
-                let inprogress_html = Render::inprogress_state(&fmt_timer, &self.board, None);
-                self.broadcast_state(inprogress_html);
+        self.broadcast_presence();
+        true
+    }
+
+    // Solo rooms are for the one player only
+    pub fn add_bot(&mut self, difficulty: Difficulty) -> bool {
+        if !self.in_lobby() || self.is_solo() || self.players.bots().len() >= Self::MAX_BOTS {
+            return false;
+        }
+
+        let number = (1..)
+            .find(|n| !self.players.contains_key(&PlayerId(format!("bot-{}", n))))
+            .unwrap_or(1);
+        let bot_id = PlayerId(format!("bot-{}", number));
+        let username = PlayerId(format!("{} bot {}", difficulty.label(), number));
+
+        self.players.add_bot(bot_id.clone(), username, difficulty);
+        self.players.assign_team(&bot_id, self.settings.teams);
+        self.broadcast_presence();
+        true
+    }
+
+    // Each bot works out its words for the round up front and submits them one at a time
+    fn start_bots(&self, boggle: Weak<Mutex<Self>>) {
+        for (bot_id, bot) in self.players.bots() {
+            let Some(difficulty) = bot.bot else {
+                continue;
+            };
+            let profile = difficulty.profile();
+            let words = profile.plan(
+                &self.board.words,
+                self.settings.duration,
+                &mut rand::thread_rng(),
+            );
+
+            tokio::spawn(Self::run_bot(
+                boggle.clone(),
+                bot_id.clone(),
+                words,
+                profile.interval(),
+                self.game,
+            ));
+        }
+    }
+
+    async fn run_bot(
+        boggle: Weak<Mutex<Self>>,
+        bot_id: PlayerId,
+        words: Vec<String>,
+        interval: Duration,
+        game: u64,
+    ) {
+        for word in words {
+            loop {
+                tokio::time::sleep(interval).await;
+                let Some(boggle) = boggle.upgrade() else {
+                    return;
+                };
+                let mut boggle = boggle.lock().await;
+                if boggle.game != game || !boggle.players.is_active(&bot_id) {
+                    return;
+                }
+                match boggle.state {
+                    // Wait out the pause without losing the word
+                    BoggleStateEnum::Paused => continue,
+                    BoggleStateEnum::InProgress => {
+                        boggle.submit_word(&bot_id, &word);
+                        break;
+                    }
+                    _ => return,
+                }
             }
         }
     }
 
+    // Rolls the board off the async runtime, since a strict policy can mean
+    // solving hundreds of boards, then counts down to the round. Nothing happens
+    // while a round is on or once the player has had their go at a fixed board.
+    pub async fn new_game(
+        boggle: &Arc<Mutex<Self>>,
+        setup: &BoardSetup,
+    ) -> Result<(), BoardSetupError> {
+        let Some(game) = boggle.lock().await.game_setup(setup) else {
+            return Ok(());
+        };
+
+        let board = tokio::task::spawn_blocking(move || {
+            Board::setup(
+                &game.dictionary,
+                &game.setup,
+                game.min_word_length,
+                &game.scoring,
+            )
+        })
+        .await
+        .expect("Board generation panicked")?;
+
+        boggle.lock().await.start_game(board).await;
+        Ok(())
+    }
+
+    fn game_setup(&self, setup: &BoardSetup) -> Option<GameSetup> {
+        if !self.in_lobby() || self.played_fixed_board() {
+            return None;
+        }
+
+        Some(GameSetup {
+            dictionary: self.dictionaries.get(&self.settings.dictionary),
+            setup: self.fixed_setup().unwrap_or_else(|| setup.clone()),
+            min_word_length: self.settings.min_word_length,
+            scoring: self.settings.scoring.clone(),
+        })
+    }
+
+    // Daily and challenge boards are played once, whatever was asked for
+    fn fixed_setup(&self) -> Option<BoardSetup> {
+        if let Some(daily) = &self.daily {
+            return Some(daily.setup());
+        }
+        self.challenge
+            .as_ref()?
+            .challenge()
+            .map(|challenge| challenge.setup())
+    }
+
+    fn played_fixed_board(&self) -> bool {
+        if let Some((daily, player_id)) = self.daily_player() {
+            return daily.has_played(player_id);
+        }
+        if let Some((attempt, player_id)) = self.challenge_player() {
+            return attempt
+                .challenge()
+                .is_none_or(|challenge| challenge.result(player_id).is_some());
+        }
+        false
+    }
+
+    // The room can change while the board is rolled, so it's checked again
+    async fn start_game(&mut self, board: Board) {
+        if !self.in_lobby() || self.played_fixed_board() {
+            return;
+        }
+
+        if let Some((daily, player_id)) = self.daily_player() {
+            if let Some(player) = self.players.get(player_id) {
+                daily.start(player_id, &player.username);
+            }
+        }
+
+        // A finished match starts over
+        if self.round >= self.settings.rounds {
+            self.round = 0;
+            self.players.clear_match();
+        }
+        self.round += 1;
+        self.game += 1;
+
+        self.players.remove_inactive();
+        self.players.clear_state();
+        self.challenges_sent.clear();
+        self.timer
+            .lock()
+            .await
+            .countdown(Self::COUNTDOWN_SECONDS)
+            .await;
+
+        self.state = BoggleStateEnum::Countdown;
+        self.board = board;
+
+        let countdown_html = Render::countdown_state(&Self::COUNTDOWN_SECONDS.to_string());
+        self.broadcast_state(countdown_html);
+    }
+
+    async fn start_round(&mut self) {
+        {
+            let mut timer = self.timer.lock().await;
+            timer.set_duration(self.settings.duration);
+            timer.start().await;
+        }
+
+        self.send_round(&self.format_remaining(self.settings.duration));
+    }
+
+    // In team play everyone sees the words their whole team has found
+    fn found_words(&self, player_id: &PlayerId) -> Option<WordList> {
+        let player = self.players.get(player_id)?;
+        if self.settings.team_play() {
+            Some(self.players.team_words(player.team))
+        } else {
+            Some(player.words.clone())
+        }
+    }
+
+    fn format_remaining(&self, remaining: u32) -> String {
+        if self.settings.untimed() {
+            "Untimed".to_string()
+        } else {
+            Timer::format_time(remaining)
+        }
+    }
+
+    // Each player gets their own found words and hints, and the host the pause control
+    fn send_round(&self, fmt_timer: &str) {
+        self.send_each(|player_id| self.round_view(player_id, fmt_timer));
+    }
+
+    fn round_view(&self, player_id: &PlayerId, fmt_timer: &str) -> String {
+        let round = Render::inprogress_state(
+            &self.code,
+            fmt_timer,
+            &self.board,
+            self.found_words(player_id).as_ref(),
+            &self.players,
+            &self.settings,
+            self.is_host(player_id),
+        );
+        format!("{}{}", round, self.hints_panel(player_id))
+    }
+
+    // Spectators, bots and rooms without hints get an empty panel
+    fn hints_panel(&self, player_id: &PlayerId) -> String {
+        match self.players.get(player_id) {
+            Some(player) if self.settings.hints_enabled() && !player.is_bot() => {
+                let remaining = self
+                    .settings
+                    .hints
+                    .saturating_sub(player.hints.len() as u32);
+                Render::hints(&self.code, &player.hints, remaining)
+            }
+            _ => Render::hints(&self.code, &[], 0),
+        }
+    }
+
+    // Returns the player's updated hints, or None if they can't have another
+    pub fn request_hint(&mut self, player_id: &PlayerId, kind: HintKind) -> Option<String> {
+        if self.state != BoggleStateEnum::InProgress || !self.players.is_human(player_id) {
+            return None;
+        }
+
+        let found_words = self.found_words(player_id)?;
+        let player = self.players.get_mut(player_id)?;
+        if player.hints.len() as u32 >= self.settings.hints {
+            return None;
+        }
+
+        let hint = Hint::new(kind, &self.board.words, &found_words)?;
+        player.hints.push(hint);
+        Some(self.hints_panel(player_id))
+    }
+
+    pub async fn pause(&mut self) -> bool {
+        if self.state != BoggleStateEnum::InProgress {
+            return false;
+        }
+
+        let remaining = {
+            let timer = self.timer.lock().await;
+            timer.pause();
+            timer.remaining()
+        };
+        self.state = BoggleStateEnum::Paused;
+
+        let fmt_timer = self.format_remaining(remaining);
+        self.send_each(|player_id| {
+            Render::paused_state(&self.code, &fmt_timer, self.is_host(player_id))
+        });
+        true
+    }
+
+    pub async fn resume(&mut self) -> bool {
+        if self.state != BoggleStateEnum::Paused {
+            return false;
+        }
+
+        let remaining = {
+            let timer = self.timer.lock().await;
+            timer.resume().await;
+            timer.remaining()
+        };
+        self.state = BoggleStateEnum::InProgress;
+
+        self.send_round(&self.format_remaining(remaining));
+        true
+    }
+
+    // Finishes the round early, and is the only way an untimed round ends
+    pub async fn end_round(&mut self) -> bool {
+        if !matches!(
+            self.state,
+            BoggleStateEnum::InProgress | BoggleStateEnum::Paused
+        ) {
+            return false;
+        }
+
+        self.timer.lock().await.cancel();
+        self.state = BoggleStateEnum::GameOver;
+        self.game_over();
+        true
+    }
+
+    // Returns whether the player is now ready
+    pub fn toggle_ready(&mut self, player_id: &PlayerId) -> bool {
+        if !self.in_lobby() {
+            return false;
+        }
+
+        let ready = self.players.toggle_ready(player_id);
+        self.broadcast_presence();
+        ready
+    }
+
+    // Starts the countdown once every connected player is ready. Nobody asked
+    // for this board, so the host hears about it if it can't be rolled
+    pub async fn start_when_ready(boggle: &Arc<Mutex<Self>>) {
+        let setup = {
+            let boggle = boggle.lock().await;
+            if !boggle.in_lobby() || !boggle.players.all_ready() {
+                return;
+            }
+            boggle.settings.roll(None)
+        };
+
+        if let Err(e) = Self::new_game(boggle, &setup).await {
+            let boggle = boggle.lock().await;
+            if let Some(host) = boggle
+                .host
+                .as_ref()
+                .and_then(|host| boggle.players.get(host))
+            {
+                host.send(Render::setup_error(&e.to_string()));
+            }
+        }
+    }
+
+    pub fn switch_team(&mut self, player_id: &PlayerId) -> bool {
+        if !self.in_lobby() || !self.settings.team_play() {
+            return false;
+        }
+
+        self.players.switch_team(player_id, self.settings.teams);
+        self.broadcast_presence();
+        true
+    }
+
+    // Players gather and ready up before a round and after one ends
+    fn in_lobby(&self) -> bool {
+        matches!(
+            self.state,
+            BoggleStateEnum::Starting | BoggleStateEnum::GameOver
+        )
+    }
+
+    // Sent when players join, leave or ready up, or the settings or host change.
+    // Only the host sees host controls, so every player gets their own copy
+    fn broadcast_presence(&self) {
+        match self.state {
+            BoggleStateEnum::Starting | BoggleStateEnum::GameOver => {
+                self.send_each(|player_id| self.lobby_panel(player_id));
+            }
+            BoggleStateEnum::InProgress | BoggleStateEnum::Paused => {
+                let paused = self.state == BoggleStateEnum::Paused;
+                self.send_each(|player_id| {
+                    Render::round_controls(&self.code, paused, self.is_host(player_id))
+                });
+            }
+            BoggleStateEnum::Countdown => (),
+        }
+    }
+
     fn game_over(&mut self) {
         self.total_scores();
-        let game_over_html = Render::gameover_state(&self.board, &self.players);
+        self.players.add_round_scores();
 
-        self.broadcast_state(game_over_html);
+        if let Some((daily, player_id)) = self.daily_player() {
+            if let Some(player) = self.players.get(player_id) {
+                daily.finish(player_id, player.words.total_score, player.words.len());
+            }
+        }
+        if let Some((attempt, player_id)) = self.challenge_player() {
+            if let Some(player) = self.players.get(player_id) {
+                attempt.record(ChallengeResult::new(
+                    player_id,
+                    &player.username,
+                    player.score,
+                    &player.words,
+                ));
+            }
+        }
+
+        self.send_each(|player_id| self.gameover_state(player_id));
+    }
+
+    // Daily and challenge boards have their own results, so only other rooms offer a challenge link
+    fn gameover_state(&self, player_id: &PlayerId) -> String {
+        let round_results = Render::round_results(
+            &self.code,
+            &self.board,
+            &self.players,
+            &self.settings,
+            self.round,
+            player_id,
+            !self.fixed_board(),
+        );
+        Render::gameover_state(
+            &self.board,
+            &self.lobby_panel(player_id),
+            &round_results,
+            self.players.get(player_id).map(|player| &player.words),
+        )
+    }
+
+    // The player's last round, ready to send to someone who wasn't there.
+    // Daily and challenge boards already have a place to compare results
+    pub fn challenge_from(&self, player_id: &PlayerId) -> Option<Challenge> {
+        if self.state != BoggleStateEnum::GameOver || self.fixed_board() {
+            return None;
+        }
+
+        let player = self.players.get(player_id)?;
+        Some(Challenge::new(
+            self.board.notation(),
+            &self.settings,
+            ChallengeResult::new(player_id, &player.username, player.score, &player.words),
+        ))
+    }
+
+    pub fn challenge_sent(&self, player_id: &PlayerId) -> Option<ChallengeId> {
+        self.challenges_sent.get(player_id).cloned()
+    }
+
+    // The first link wins if the player asked twice at once
+    pub fn record_challenge(&mut self, player_id: &PlayerId, id: ChallengeId) -> ChallengeId {
+        if self.state != BoggleStateEnum::GameOver {
+            return id;
+        }
+        self.challenges_sent
+            .entry(player_id.clone())
+            .or_insert(id)
+            .clone()
+    }
+
+    // Settings can only change between rounds
+    pub fn update_settings(&mut self, settings: GameSettings) -> Result<(), SettingsError> {
+        if !self.in_lobby() || self.fixed_board() {
+            return Err(SettingsError::Locked);
+        }
+
+        settings.validate()?;
+        let settings = settings.normalize(&self.dictionaries);
+        if settings.teams != self.settings.teams {
+            self.players.assign_teams(settings.teams);
+        }
+        self.settings = settings;
+        self.broadcast_presence();
+        Ok(())
     }
 
     fn total_scores(&mut self) {
+        if self.settings.scoring_mode == ScoringMode::CancelShared {
+            self.players.cancel_shared_words();
+        }
+
+        self.board.words.total_words(&self.settings.scoring);
         for player in self.players.values_mut() {
-            player.words.total_words();
+            player.words.total_words(&self.settings.scoring);
         }
     }
 
     pub fn submit_word(&mut self, player_id: &PlayerId, word: &str) -> String {
         let sanitized_word = word.trim().to_uppercase();
+        let result = self.add_word(player_id, &sanitized_word);
+
+        if result.is_accepted() {
+            let scoreboard_html = Render::scoreboard(&self.players, &self.settings);
+            self.broadcast_state(scoreboard_html);
 
-        if !Board::is_valid_word(&sanitized_word) {
-            return Render::invalid_word_submission();
+            if self.settings.team_play() {
+                for (_, teammate) in self.players.teammates(player_id) {
+                    teammate.send(Render::team_word(&sanitized_word));
+                }
+            }
         }
 
-        let player = match self.players.get_mut(player_id) {
-            Some(player) => player,
-            None => return Render::invalid_word_submission(),
-        };
+        Render::submission(
+            &self.code,
+            &sanitized_word,
+            result,
+            self.settings.min_word_length,
+        )
+    }
+
+    // Adds the word to the player's list if it counts
+    pub fn add_word(&mut self, player_id: &PlayerId, word: &str) -> SubmissionResult {
+        if self.state != BoggleStateEnum::InProgress {
+            return SubmissionResult::GameNotRunning;
+        }
+        if !self.players.contains_key(player_id) {
+            return SubmissionResult::NotAPlayer;
+        }
+        if !Board::is_well_formed(word) {
+            return SubmissionResult::Malformed;
+        }
+        if word.len() < self.settings.min_word_length {
+            return SubmissionResult::TooShort(self.settings.min_word_length);
+        }
 
-        player
+        // Teammates who left mid-round still hold their words
+        let found_by_team =
+            self.settings.team_play() && self.players.team_has_found(player_id, word);
+
+        let Some(player) = self.players.get_mut(player_id) else {
+            return SubmissionResult::NotAPlayer;
+        };
+        if found_by_team || player.words.contains(word) {
+            return SubmissionResult::AlreadyFound;
+        }
+        if player
             .words
-            .add_from_board_if_not_exists(&sanitized_word, &self.board.words);
+            .add_from_board_if_not_exists(word, &self.board.words)
+        {
+            player.score += self.settings.scoring.score(word);
+            return SubmissionResult::Accepted;
+        }
 
-        Render::word_submit(sanitized_word)
+        let dictionary = self.dictionaries.get(&self.settings.dictionary);
+        match dictionary.search(&word.to_lowercase()) {
+            SearchResult::ValidWord(_) => SubmissionResult::NotOnBoard,
+            _ => SubmissionResult::NotInDictionary,
+        }
     }
 
     pub async fn set_state_to_starting(&mut self) {
         match self.state {
             BoggleStateEnum::Starting => (),
-            BoggleStateEnum::InProgress => {
+            BoggleStateEnum::Countdown | BoggleStateEnum::InProgress => {
                 self.timer.lock().await.cancel();
                 self.state = BoggleStateEnum::Starting;
             }
-            BoggleStateEnum::GameOver => {
+            // A paused round has no timer running
+            BoggleStateEnum::Paused | BoggleStateEnum::GameOver => {
                 self.state = BoggleStateEnum::Starting;
             }
         }
     }
 
-    pub async fn start_game_loop(boggle: Arc<Mutex<Self>>) {
-        let mut boggle_rx = {
-            let state = boggle.lock().await;
-            state.boggle_channel_tx.subscribe()
-        };
+    // Stop the round before the room is dropped
+    pub async fn close(&mut self) {
+        self.set_state_to_starting().await;
+    }
+
+    pub fn is_abandoned(&self, timeout: Duration) -> bool {
+        self.players.is_empty() && self.created_at.elapsed() > timeout
+    }
 
+    // Runs until the room is dropped and the channel closes. The room can change
+    // while an event waits for the lock, like a round the host already ended or
+    // paused, so only events from the running count move the game on
+    pub async fn start_game_loop(
+        boggle: Weak<Mutex<Self>>,
+        mut boggle_rx: broadcast::Receiver<TimerEvent>,
+    ) {
         loop {
-            tokio::select! {
-                Ok(new_state) = boggle_rx.recv() => {
+            match boggle_rx.recv().await {
+                Ok(event) => {
+                    let Some(boggle) = boggle.upgrade() else {
+                        break;
+                    };
                     let mut state = boggle.lock().await;
-                    state.state = new_state;
-                    match new_state {
-                        BoggleStateEnum::GameOver => {
+                    if !state.timer.lock().await.is_current(&event) {
+                        continue;
+                    }
+                    match (state.state, event.state) {
+                        (BoggleStateEnum::Countdown, BoggleStateEnum::InProgress) => {
+                            state.state = BoggleStateEnum::InProgress;
+                            state.start_round().await;
+                            state.start_bots(Arc::downgrade(&boggle));
+                        }
+                        (
+                            BoggleStateEnum::InProgress | BoggleStateEnum::Paused,
+                            BoggleStateEnum::GameOver,
+                        ) => {
+                            state.state = BoggleStateEnum::GameOver;
                             state.game_over();
-                        },
-                        _ => {}
+                        }
+                        _ => (),
                     }
-                },
+                }
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
             }
         }
     }
 
-    pub async fn get_player_score(&self, username: PlayerId) -> String {
-        if username.0 == "Board Total" {
+    // Anyone in the room can go through the results once the round is over, but
+    // during a round players only see their own and their teammates' words
+    pub fn get_player_score(&self, viewer: &PlayerId, username: PlayerId) -> Option<String> {
+        match self.state {
+            BoggleStateEnum::GameOver => {
+                if !self.players.contains_key(viewer) && !self.spectators.contains_key(viewer) {
+                    return None;
+                }
+            }
+            BoggleStateEnum::InProgress | BoggleStateEnum::Paused => {
+                let player = self.players.get(viewer)?;
+                let teammate = self.settings.team_play()
+                    && self
+                        .players
+                        .get(&username)
+                        .is_some_and(|other| other.team == player.team);
+                if &username != viewer && !teammate {
+                    return None;
+                }
+            }
+            _ => return None,
+        }
+
+        let html = if username.0 == "Board Total" {
             Render::valid_words(&self.board.words)
         } else {
             match self.players.get(&username) {
@@ -175,6 +916,18 @@ impl Boggle {
                     markup.into_string()
                 }
             }
+        };
+        Some(html)
+    }
+
+    fn send_each(&self, render: impl Fn(&PlayerId) -> String) {
+        for (player_id, player) in self.players.get_active_players_sorted_by_name() {
+            player.send(render(player_id));
+        }
+        for (spectator_id, sender) in &self.spectators {
+            if let Err(e) = sender.send(Message::Text(render(spectator_id))) {
+                eprintln!("Failed to send to spectator: {}", e);
+            }
         }
     }
 
@@ -184,3 +937,366 @@ impl Boggle {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
+
+    fn room() -> Arc<Mutex<Boggle>> {
+        let dictionaries = Arc::new(Dictionaries::from_words(&["cat", "act"]));
+        Boggle::new(RoomCode("TEST".to_string()), dictionaries, None, None, None)
+    }
+
+    fn solo_room(player: &str) -> Arc<Mutex<Boggle>> {
+        let dictionaries = Arc::new(Dictionaries::from_words(&["cat", "act"]));
+        Boggle::new(
+            RoomCode("SOLO".to_string()),
+            dictionaries,
+            Some(PlayerId::from(player)),
+            None,
+            None,
+        )
+    }
+
+    fn watch(boggle: &mut Boggle, name: &str) -> (bool, UnboundedReceiver<Message>) {
+        let (sender, receiver) = unbounded_channel();
+        (boggle.watch(&PlayerId::from(name), sender), receiver)
+    }
+
+    // The receiver is handed back so messages to the player have somewhere to go
+    fn join(boggle: &mut Boggle, name: &str) -> (bool, UnboundedReceiver<Message>) {
+        let (sender, receiver) = unbounded_channel();
+        (
+            boggle.join(&PlayerId::from(name), sender, PlayerId::from(name)),
+            receiver,
+        )
+    }
+
+    #[tokio::test]
+    async fn first_player_in_hosts() {
+        let room = room();
+        let mut boggle = room.lock().await;
+        let _ann = join(&mut boggle, "ann");
+        let _bob = join(&mut boggle, "bob");
+        assert!(boggle.is_host(&PlayerId::from("ann")));
+        assert!(!boggle.is_host(&PlayerId::from("bob")));
+    }
+
+    #[tokio::test]
+    async fn host_passes_to_the_next_person_not_a_bot() {
+        let room = room();
+        let mut boggle = room.lock().await;
+        let _cat = join(&mut boggle, "cat");
+        assert!(boggle.add_bot(Difficulty::default()));
+        let _dan = join(&mut boggle, "dan");
+
+        boggle.leave(&PlayerId::from("cat"));
+        assert!(boggle.is_host(&PlayerId::from("dan")));
+
+        // Coming back doesn't take the room back
+        let _cat = join(&mut boggle, "cat");
+        assert!(boggle.is_host(&PlayerId::from("dan")));
+    }
+
+    #[tokio::test]
+    async fn host_goes_to_whoever_returns_to_an_empty_room() {
+        let room = room();
+        let mut boggle = room.lock().await;
+        let _ann = join(&mut boggle, "ann");
+        boggle.leave(&PlayerId::from("ann"));
+        assert!(!boggle.is_host(&PlayerId::from("ann")));
+
+        let _bob = join(&mut boggle, "bob");
+        assert!(boggle.is_host(&PlayerId::from("bob")));
+    }
+
+    #[tokio::test]
+    async fn host_only_transfers_to_connected_people() {
+        let room = room();
+        let mut boggle = room.lock().await;
+        let _ann = join(&mut boggle, "ann");
+        let _bob = join(&mut boggle, "bob");
+        boggle.add_bot(Difficulty::default());
+
+        assert!(!boggle.transfer_host(&PlayerId::from("bot-1")));
+        assert!(!boggle.transfer_host(&PlayerId::from("nobody")));
+        assert!(boggle.transfer_host(&PlayerId::from("bob")));
+        assert!(boggle.is_host(&PlayerId::from("bob")));
+    }
+
+    #[tokio::test]
+    async fn banned_players_stay_out() {
+        let room = room();
+        let mut boggle = room.lock().await;
+        let _ann = join(&mut boggle, "ann");
+        let _bob = join(&mut boggle, "bob");
+        let _eve = join(&mut boggle, "eve");
+
+        assert!(!boggle.kick(&PlayerId::from("ann"), true));
+        assert!(boggle.kick(&PlayerId::from("bob"), false));
+        assert!(join(&mut boggle, "bob").0);
+        assert!(boggle.kick(&PlayerId::from("eve"), true));
+        assert!(!join(&mut boggle, "eve").0);
+    }
+
+    #[tokio::test]
+    async fn spectators_are_not_players() {
+        let room = room();
+        let mut boggle = room.lock().await;
+        let _ann = join(&mut boggle, "ann");
+        let (watching, _sam) = watch(&mut boggle, "sam");
+        assert!(watching);
+        assert!(!boggle.players.contains_key(&PlayerId::from("sam")));
+        assert!(!boggle.is_host(&PlayerId::from("sam")));
+
+        boggle.state = BoggleStateEnum::InProgress;
+        assert_eq!(
+            boggle.add_word(&PlayerId::from("sam"), "CAT"),
+            SubmissionResult::NotAPlayer
+        );
+    }
+
+    #[tokio::test]
+    async fn spectators_see_the_room_and_strangers_do_not() {
+        let room = room();
+        let mut boggle = room.lock().await;
+        let _ann = join(&mut boggle, "ann");
+        let _sam = watch(&mut boggle, "sam");
+        assert_ne!(
+            boggle.get_game_state(&PlayerId::from("sam")).await,
+            "Player not found"
+        );
+        assert_eq!(
+            boggle.get_game_state(&PlayerId::from("zed")).await,
+            "Player not found"
+        );
+
+        boggle.stop_watching(&PlayerId::from("sam"));
+        assert_eq!(
+            boggle.get_game_state(&PlayerId::from("sam")).await,
+            "Player not found"
+        );
+    }
+
+    #[tokio::test]
+    async fn spectators_see_words_once_the_round_is_over() {
+        let room = room();
+        let mut boggle = room.lock().await;
+        let _ann = join(&mut boggle, "ann");
+        let _sam = watch(&mut boggle, "sam");
+
+        boggle.state = BoggleStateEnum::InProgress;
+        assert!(boggle
+            .get_player_score(&PlayerId::from("sam"), PlayerId::from("ann"))
+            .is_none());
+        boggle.state = BoggleStateEnum::GameOver;
+        assert!(boggle
+            .get_player_score(&PlayerId::from("sam"), PlayerId::from("ann"))
+            .is_some());
+        assert!(boggle
+            .get_player_score(&PlayerId::from("zed"), PlayerId::from("ann"))
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn banned_players_cannot_watch() {
+        let room = room();
+        let mut boggle = room.lock().await;
+        let _ann = join(&mut boggle, "ann");
+        let _eve = join(&mut boggle, "eve");
+        boggle.kick(&PlayerId::from("eve"), true);
+        assert!(!watch(&mut boggle, "eve").0);
+
+        boggle.state = BoggleStateEnum::GameOver;
+        assert!(boggle
+            .get_player_score(&PlayerId::from("eve"), PlayerId::from("ann"))
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn nobody_watches_a_solo_room() {
+        let room = solo_room("ann");
+        let mut boggle = room.lock().await;
+        assert!(!watch(&mut boggle, "sam").0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn a_finished_match_starts_over() {
+        let room = room();
+        let mut boggle = room.lock().await;
+        let _ann = join(&mut boggle, "ann");
+        boggle.settings.rounds = 2;
+
+        for (round, match_score) in [(1, 1), (2, 2), (1, 1)] {
+            let board = Board::empty(&boggle.dictionaries.get(Dictionaries::DEFAULT));
+            boggle.start_game(board).await;
+            assert_eq!(boggle.round, round);
+
+            let ann = boggle.players.get_mut(&PlayerId::from("ann")).unwrap();
+            ann.words.add("CAT", String::new(), Vec::new());
+            boggle.game_over();
+            boggle.state = BoggleStateEnum::GameOver;
+            let ann = boggle.players.get(&PlayerId::from("ann")).unwrap();
+            assert_eq!(ann.match_score, match_score);
+        }
+    }
+
+    #[tokio::test]
+    async fn teammates_see_each_others_words_during_a_round() {
+        let room = room();
+        let mut boggle = room.lock().await;
+        boggle.settings.teams = 2;
+        let _ann = join(&mut boggle, "ann");
+        let _bob = join(&mut boggle, "bob");
+        let _cat = join(&mut boggle, "cat");
+        boggle.players.assign_teams(2);
+
+        boggle.state = BoggleStateEnum::InProgress;
+        assert!(boggle
+            .get_player_score(&PlayerId::from("ann"), PlayerId::from("cat"))
+            .is_some());
+        assert!(boggle
+            .get_player_score(&PlayerId::from("ann"), PlayerId::from("bob"))
+            .is_none());
+        assert!(boggle
+            .get_player_score(&PlayerId::from("ann"), PlayerId::from("ann"))
+            .is_some());
+    }
+
+    // Puts the room in a round on a board where CAT and ACT can be found
+    fn play(boggle: &mut Boggle) {
+        let dictionary = boggle.dictionaries.get(Dictionaries::DEFAULT);
+        boggle.board = Board::from_notation(&dictionary, "CAXX/TXXX/XXXX/XXXX").unwrap();
+        boggle.state = BoggleStateEnum::InProgress;
+    }
+
+    #[tokio::test]
+    async fn teams_keep_the_words_of_teammates_who_left() {
+        let room = room();
+        let mut boggle = room.lock().await;
+        boggle.settings.teams = 2;
+        let _ann = join(&mut boggle, "ann");
+        let _bob = join(&mut boggle, "bob");
+        let _cat = join(&mut boggle, "cat");
+        boggle.players.assign_teams(2);
+        play(&mut boggle);
+
+        assert_eq!(
+            boggle.add_word(&PlayerId::from("ann"), "CAT"),
+            SubmissionResult::Accepted
+        );
+        boggle.leave(&PlayerId::from("ann"));
+        assert_eq!(
+            boggle.add_word(&PlayerId::from("cat"), "CAT"),
+            SubmissionResult::AlreadyFound
+        );
+        assert_eq!(
+            boggle.add_word(&PlayerId::from("bob"), "CAT"),
+            SubmissionResult::Accepted
+        );
+    }
+
+    #[tokio::test]
+    async fn submissions_say_why_a_word_was_turned_down() {
+        let room = room();
+        let mut boggle = room.lock().await;
+        let _ann = join(&mut boggle, "ann");
+        assert_eq!(
+            boggle.add_word(&PlayerId::from("ann"), "CAT"),
+            SubmissionResult::GameNotRunning
+        );
+
+        play(&mut boggle);
+        boggle.settings.min_word_length = 3;
+        assert_eq!(
+            boggle.add_word(&PlayerId::from("ann"), "CAT"),
+            SubmissionResult::Accepted
+        );
+        assert_eq!(
+            boggle.add_word(&PlayerId::from("ann"), "CAT"),
+            SubmissionResult::AlreadyFound
+        );
+        assert_eq!(
+            boggle.add_word(&PlayerId::from("ann"), "TACT"),
+            SubmissionResult::NotInDictionary
+        );
+        assert_eq!(
+            boggle.add_word(&PlayerId::from("ann"), "AT"),
+            SubmissionResult::TooShort(3)
+        );
+        for word in ["C T", "CAT1", &"A".repeat(Board::MAX_WORD_LENGTH + 1)] {
+            assert_eq!(
+                boggle.add_word(&PlayerId::from("ann"), word),
+                SubmissionResult::Malformed
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn dictionary_words_missing_from_the_board_are_told_apart() {
+        let dictionaries = Arc::new(Dictionaries::from_words(&["cat", "dog"]));
+        let room = Boggle::new(RoomCode("TEST".to_string()), dictionaries, None, None, None);
+        let mut boggle = room.lock().await;
+        let _ann = join(&mut boggle, "ann");
+        play(&mut boggle);
+        assert_eq!(
+            boggle.add_word(&PlayerId::from("ann"), "DOG"),
+            SubmissionResult::NotOnBoard
+        );
+        assert_eq!(
+            boggle.add_word(&PlayerId::from("ann"), "COW"),
+            SubmissionResult::NotInDictionary
+        );
+    }
+
+    #[tokio::test]
+    async fn cancel_rounds_only_score_words_nobody_else_found() {
+        let room = room();
+        let mut boggle = room.lock().await;
+        boggle.settings.scoring_mode = ScoringMode::CancelShared;
+        let _ann = join(&mut boggle, "ann");
+        let _bob = join(&mut boggle, "bob");
+        play(&mut boggle);
+        boggle.add_word(&PlayerId::from("ann"), "CAT");
+        boggle.add_word(&PlayerId::from("ann"), "ACT");
+        boggle.add_word(&PlayerId::from("bob"), "CAT");
+
+        // Live scores count everything until the round is scored
+        assert_eq!(boggle.players.get(&PlayerId::from("bob")).unwrap().score, 1);
+        boggle.total_scores();
+
+        let ann = &boggle.players.get(&PlayerId::from("ann")).unwrap().words;
+        assert!(ann.get("CAT").unwrap().cancelled);
+        assert!(!ann.get("ACT").unwrap().cancelled);
+        assert_eq!(ann.total_score, 1);
+        let bob = &boggle.players.get(&PlayerId::from("bob")).unwrap().words;
+        assert!(bob.get("CAT").unwrap().cancelled);
+        assert_eq!(bob.total_score, 0);
+    }
+
+    #[tokio::test]
+    async fn solo_rooms_only_admit_their_player() {
+        let room = solo_room("ann");
+        let mut boggle = room.lock().await;
+        assert!(!join(&mut boggle, "bob").0);
+        assert!(join(&mut boggle, "ann").0);
+        assert!(boggle.is_host(&PlayerId::from("ann")));
+
+        // Leaving and coming back is fine
+        boggle.leave(&PlayerId::from("ann"));
+        assert!(join(&mut boggle, "ann").0);
+    }
+
+    #[tokio::test]
+    async fn solo_rooms_have_no_bots() {
+        let solo = solo_room("ann");
+        let mut boggle = solo.lock().await;
+        let _ann = join(&mut boggle, "ann");
+        assert!(!boggle.add_bot(Difficulty::default()));
+
+        let open = room();
+        let mut boggle = open.lock().await;
+        assert!(boggle.add_bot(Difficulty::default()));
+    }
+}
@@ -0,0 +1,162 @@
+use super::form_value::FormValue;
+use super::scoring::{Scoring, ScoringRule};
+use super::settings::GameSettings;
+use super::word_list::WordList;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+// How well a computer opponent plays
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Medium,
+    Hard,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Medium, Difficulty::Hard];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Medium => "Medium",
+            Difficulty::Hard => "Hard",
+        }
+    }
+
+    pub fn profile(&self) -> BotProfile {
+        match self {
+            Difficulty::Easy => BotProfile {
+                words_per_minute: 4.0,
+                max_rarity: 1.8,
+                length_bias: -1.0,
+            },
+            Difficulty::Medium => BotProfile {
+                words_per_minute: 8.0,
+                max_rarity: 2.5,
+                length_bias: 0.0,
+            },
+            Difficulty::Hard => BotProfile {
+                words_per_minute: 15.0,
+                max_rarity: f32::MAX,
+                length_bias: 1.0,
+            },
+        }
+    }
+}
+
+impl FormValue for Difficulty {}
+
+#[derive(Debug, Clone, Copy)]
+pub struct BotProfile {
+    pub words_per_minute: f32,
+    // Highest average letter rarity of a word the bot knows, so weaker bots
+    // miss words with J, Q, X and Z
+    pub max_rarity: f32,
+    // Above 0 the bot goes for long words first, below 0 for short ones
+    pub length_bias: f32,
+}
+
+impl BotProfile {
+    pub fn knows(&self, word: &str) -> bool {
+        let rarity = Scoring::Rarity.score(word) as f32 / word.len() as f32;
+        rarity <= self.max_rarity
+    }
+
+    // Time between two words
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs_f32(60.0 / self.words_per_minute)
+    }
+
+    // The words the bot will find this round, in the order it finds them. An
+    // untimed round lasts until the host ends it, so the bot keeps going
+    // through every word it knows
+    pub fn plan(&self, board_words: &WordList, duration: u32, rng: &mut impl Rng) -> Vec<String> {
+        let count = if duration == GameSettings::UNTIMED {
+            usize::MAX
+        } else {
+            (self.words_per_minute * duration as f32 / 60.0) as usize
+        };
+        // Weighted shuffle: each word draws a key and the weight decides how
+        // likely it is to come out near the front
+        let mut keyed: Vec<(f32, &str)> = board_words
+            .iter()
+            .map(|found| found.word.as_str())
+            .filter(|word| self.knows(word))
+            .map(|word| {
+                let weight = (word.len() as f32).powf(self.length_bias * 2.0);
+                (rng.gen::<f32>().powf(1.0 / weight), word)
+            })
+            .collect();
+        keyed.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+
+        keyed
+            .into_iter()
+            .take(count)
+            .map(|(_, word)| word.to_string())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    fn rng() -> ChaCha8Rng {
+        ChaCha8Rng::seed_from_u64(42)
+    }
+
+    #[test]
+    fn weaker_bots_miss_rare_letters() {
+        assert!(Difficulty::Easy.profile().knows("TEN"));
+        assert!(!Difficulty::Easy.profile().knows("JAZZ"));
+        assert!(Difficulty::Hard.profile().knows("JAZZ"));
+    }
+
+    #[test]
+    fn plan_fits_the_round() {
+        let board_words = WordList::from_words(&["TEN", "NET", "TENT", "RENT", "STERN", "TENTS"]);
+        // Four words a minute makes two in thirty seconds
+        let plan = Difficulty::Easy
+            .profile()
+            .plan(&board_words, 30, &mut rng());
+        assert_eq!(plan.len(), 2);
+        assert!(plan.iter().all(|word| board_words.contains(word)));
+    }
+
+    #[test]
+    fn plan_only_has_words_the_bot_knows() {
+        let board_words = WordList::from_words(&["TEN", "JAZZ"]);
+        let plan = Difficulty::Easy
+            .profile()
+            .plan(&board_words, 600, &mut rng());
+        assert_eq!(plan, ["TEN"]);
+    }
+
+    #[test]
+    fn untimed_rounds_plan_every_known_word() {
+        let board_words = WordList::from_words(&["TEN", "NET", "TENT", "RENT", "STERN"]);
+        let plan = Difficulty::Easy
+            .profile()
+            .plan(&board_words, GameSettings::UNTIMED, &mut rng());
+        assert_eq!(plan.len(), 5);
+    }
+
+    #[test]
+    fn hard_bots_find_long_words_first() {
+        let board_words = WordList::from_words(&["TEN", "NET", "RENT", "STERNEST"]);
+        let mut rng = rng();
+        // The long word should come first about two times in three
+        let firsts = (0..200)
+            .filter(|_| {
+                Difficulty::Hard.profile().plan(&board_words, 60, &mut rng)[0] == "STERNEST"
+            })
+            .count();
+        assert!(firsts > 100, "long word came first {} of 200 times", firsts);
+    }
+}
@@ -0,0 +1,323 @@
+use super::store::Store;
+use crate::models::{BoardSetup, GameSettings, PlayerId, Scoring, WordList};
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::env;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const ID_CHARACTERS: &[u8] = b"abcdefghjkmnpqrstuvwxyz23456789";
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub struct ChallengeId(pub String);
+
+impl ChallengeId {
+    pub const LENGTH: usize = 8;
+
+    fn generate() -> Self {
+        let mut rng = rand::thread_rng();
+        let id = (0..Self::LENGTH)
+            .map(|_| *ID_CHARACTERS.choose(&mut rng).unwrap() as char)
+            .collect();
+        ChallengeId(id)
+    }
+}
+
+impl fmt::Display for ChallengeId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChallengeResult {
+    pub player: PlayerId,
+    pub username: PlayerId,
+    pub score: u32,
+    pub words: Vec<String>,
+}
+
+impl ChallengeResult {
+    // Takes the score before shared words were cancelled, since the friend
+    // plays the board alone with nothing to cancel against
+    pub fn new(player: &PlayerId, username: &PlayerId, score: u32, words: &WordList) -> Self {
+        Self {
+            player: player.clone(),
+            username: username.clone(),
+            score,
+            words: words.iter().map(|found| found.word.clone()).collect(),
+        }
+    }
+}
+
+// A finished round anyone with the link can play later on the same board
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Challenge {
+    pub board: String,
+    pub duration: u32,
+    pub min_word_length: usize,
+    pub dictionary: String,
+    // Kept as the form values since custom scoring tables are part of the rule
+    pub scoring: String,
+    pub custom_points: String,
+    // The player who sent the challenge comes first
+    pub results: Vec<ChallengeResult>,
+    // Seconds since the epoch, so the oldest challenges can make way for new ones
+    #[serde(default)]
+    pub created: u64,
+}
+
+impl Challenge {
+    pub fn new(board: String, settings: &GameSettings, result: ChallengeResult) -> Self {
+        Self {
+            board,
+            duration: settings.duration,
+            min_word_length: settings.min_word_length,
+            dictionary: settings.dictionary.clone(),
+            scoring: settings.scoring.value().to_string(),
+            custom_points: settings.scoring.custom_points(),
+            results: vec![result],
+            created: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_secs()),
+        }
+    }
+
+    pub fn challenger(&self) -> &ChallengeResult {
+        &self.results[0]
+    }
+
+    pub fn result(&self, player_id: &PlayerId) -> Option<&ChallengeResult> {
+        self.results
+            .iter()
+            .find(|result| &result.player == player_id)
+    }
+
+    pub fn settings(&self) -> GameSettings {
+        GameSettings {
+            duration: self.duration,
+            min_word_length: self.min_word_length,
+            dictionary: self.dictionary.clone(),
+            scoring: Scoring::from_form(&self.scoring, &self.custom_points).unwrap_or_default(),
+            ..GameSettings::default()
+        }
+    }
+
+    pub fn setup(&self) -> BoardSetup {
+        BoardSetup::Notation(self.board.clone())
+    }
+}
+
+// Every challenge sent, kept in a JSON file each so links keep working after a restart
+#[derive(Debug)]
+pub struct Challenges {
+    store: Store,
+    challenges: HashMap<ChallengeId, Challenge>,
+    // Oldest first, so a full store knows which challenge to drop without a search
+    order: VecDeque<ChallengeId>,
+}
+
+impl Challenges {
+    // Only this many are kept, the oldest links stop working past it
+    pub const MAX_CHALLENGES: usize = 10_000;
+
+    pub fn dir() -> String {
+        env::var("CHALLENGES_DIR").unwrap_or_else(|_| "/app/data/challenges".to_string())
+    }
+
+    pub fn load(dir: &str) -> Self {
+        let store = Store::new(dir.into());
+        let challenges: HashMap<ChallengeId, Challenge> = store
+            .load()
+            .into_iter()
+            .map(|(id, challenge)| (ChallengeId(id), challenge))
+            .collect();
+        let mut order: Vec<ChallengeId> = challenges.keys().cloned().collect();
+        order.sort_by_key(|id| challenges[id].created);
+        Self {
+            store,
+            challenges,
+            order: order.into(),
+        }
+    }
+
+    fn save(&self, id: &ChallengeId) {
+        if let Some(challenge) = self.challenges.get(id) {
+            self.store.save(&id.0, challenge);
+        }
+    }
+
+    pub fn add(&mut self, challenge: Challenge) -> ChallengeId {
+        let id = loop {
+            let id = ChallengeId::generate();
+            if !self.challenges.contains_key(&id) {
+                break id;
+            }
+        };
+
+        while self.challenges.len() >= Self::MAX_CHALLENGES {
+            let Some(oldest) = self.order.pop_front() else {
+                break;
+            };
+            self.challenges.remove(&oldest);
+            self.store.remove(&oldest.0);
+        }
+
+        self.challenges.insert(id.clone(), challenge);
+        self.order.push_back(id.clone());
+        self.save(&id);
+        id
+    }
+
+    pub fn get(&self, id: &ChallengeId) -> Option<&Challenge> {
+        self.challenges.get(id)
+    }
+
+    pub fn contains(&self, id: &ChallengeId) -> bool {
+        self.challenges.contains_key(id)
+    }
+
+    // Each player answers a challenge once
+    pub fn record(&mut self, id: &ChallengeId, result: ChallengeResult) {
+        let Some(challenge) = self.challenges.get_mut(id) else {
+            return;
+        };
+        if challenge.result(&result.player).is_some() {
+            return;
+        }
+
+        challenge.results.push(result);
+        self.save(id);
+    }
+}
+
+// The challenge a room was opened to play
+#[derive(Debug, Clone)]
+pub struct ChallengeAttempt {
+    pub id: ChallengeId,
+    challenges: Arc<Mutex<Challenges>>,
+}
+
+impl ChallengeAttempt {
+    pub fn new(id: ChallengeId, challenges: Arc<Mutex<Challenges>>) -> Self {
+        Self { id, challenges }
+    }
+
+    pub fn challenge(&self) -> Option<Challenge> {
+        self.challenges
+            .lock()
+            .ok()
+            .and_then(|challenges| challenges.get(&self.id).cloned())
+    }
+
+    pub fn record(&self, result: ChallengeResult) {
+        if let Ok(mut challenges) = self.challenges.lock() {
+            challenges.record(&self.id, result);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn challenges(name: &str) -> Challenges {
+        let dir = env::temp_dir().join(format!("challenges-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        Challenges::load(dir.to_str().unwrap())
+    }
+
+    fn result(name: &str, score: u32) -> ChallengeResult {
+        ChallengeResult::new(
+            &PlayerId::from(name),
+            &PlayerId::from(name),
+            score,
+            &WordList::new(),
+        )
+    }
+
+    fn challenge(created: u64) -> Challenge {
+        let mut challenge = Challenge::new(
+            "ABCD/EFGH/IJKL/MNOP".to_string(),
+            &GameSettings::default(),
+            result("ann", 10),
+        );
+        challenge.created = created;
+        challenge
+    }
+
+    #[test]
+    fn challenges_keep_their_board_and_settings() {
+        let mut challenges = challenges("add");
+        let mut settings = GameSettings {
+            duration: 120,
+            ..GameSettings::default()
+        };
+        settings.scoring = Scoring::Custom(vec![2, 3]);
+        let sent = Challenge::new(
+            "ABCD/EFGH/IJKL/MNOP".to_string(),
+            &settings,
+            result("ann", 4),
+        );
+        let first = challenges.add(sent.clone());
+        let second = challenges.add(sent);
+        assert_ne!(first, second);
+
+        let challenge = challenges.get(&first).unwrap();
+        assert_eq!(challenge.challenger().score, 4);
+        assert_eq!(challenge.settings().duration, 120);
+        assert_eq!(challenge.settings().scoring, Scoring::Custom(vec![2, 3]));
+    }
+
+    #[test]
+    fn each_player_answers_once() {
+        let mut challenges = challenges("record");
+        let challenge_id = challenges.add(challenge(0));
+        challenges.record(&challenge_id, result("bob", 3));
+        challenges.record(&challenge_id, result("bob", 30));
+        challenges.record(&challenge_id, result("ann", 30));
+
+        let challenge = challenges.get(&challenge_id).unwrap();
+        assert_eq!(challenge.results.len(), 2);
+        assert_eq!(challenge.result(&PlayerId::from("bob")).unwrap().score, 3);
+
+        // Answers to a challenge that doesn't exist go nowhere
+        challenges.record(&ChallengeId("missing".to_string()), result("bob", 3));
+    }
+
+    #[test]
+    fn saved_challenges_load_oldest_first() {
+        let dir = env::temp_dir().join(format!("challenges-{}-load", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        for (id, created) in [("newer", 5), ("older", 1)] {
+            let json = serde_json::to_string(&challenge(created)).unwrap();
+            fs::write(dir.join(format!("{}.json", id)), json).unwrap();
+        }
+
+        let challenges = Challenges::load(dir.to_str().unwrap());
+        let order: Vec<_> = challenges.order.iter().map(|id| id.0.as_str()).collect();
+        assert_eq!(order, ["older", "newer"]);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn oldest_challenges_make_way_once_full() {
+        let mut challenges = challenges("cap");
+        for n in 0..Challenges::MAX_CHALLENGES as u64 {
+            let challenge_id = ChallengeId(format!("old-{}", n));
+            challenges
+                .challenges
+                .insert(challenge_id.clone(), challenge(n + 1));
+            challenges.order.push_back(challenge_id);
+        }
+
+        let newest = challenges.add(challenge(u64::MAX));
+        assert_eq!(challenges.challenges.len(), Challenges::MAX_CHALLENGES);
+        assert!(challenges.get(&ChallengeId("old-0".to_string())).is_none());
+        assert!(challenges.get(&ChallengeId("old-1".to_string())).is_some());
+        assert!(challenges.get(&newest).is_some());
+    }
+}
@@ -0,0 +1,231 @@
+use super::store::Store;
+use crate::models::{BoardSetup, GameSettings, PlayerId};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::env;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// A UTC calendar day, counted from 1970-01-01
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Day(u64);
+
+impl Day {
+    pub fn today() -> Self {
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        Day(seconds / 86_400)
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.0
+    }
+
+    // YYYY-MM-DD, using the civil_from_days algorithm from Howard Hinnant
+    pub fn date(&self) -> String {
+        let z = self.0 + 719_468;
+        let era = z / 146_097;
+        let doe = z - era * 146_097;
+        let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = doy - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        let year = yoe + era * 400 + u64::from(month <= 2);
+        format!("{:04}-{:02}-{:02}", year, month, day)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DailyResult {
+    pub player: PlayerId,
+    pub username: PlayerId,
+    pub score: u32,
+    pub words: usize,
+}
+
+// Everyone's result on every daily board, kept in a JSON file per day between
+// restarts so a result only rewrites its own day
+#[derive(Debug)]
+pub struct DailyResults {
+    store: Store,
+    // Keyed by the date of the board
+    days: HashMap<String, Vec<DailyResult>>,
+}
+
+impl DailyResults {
+    pub fn dir() -> String {
+        env::var("DAILY_RESULTS_DIR").unwrap_or_else(|_| "/app/data/daily".to_string())
+    }
+
+    pub fn load(dir: &str) -> Self {
+        let store = Store::new(dir.into());
+        Self {
+            days: store.load(),
+            store,
+        }
+    }
+
+    fn save(&self, date: &str) {
+        if let Some(results) = self.days.get(date) {
+            self.store.save(date, results);
+        }
+    }
+
+    // Players are only known by their session, so clearing cookies gets another
+    // go at the day's board; there are no accounts to tie attempts to
+    pub fn has_played(&self, day: Day, player_id: &PlayerId) -> bool {
+        self.days
+            .get(&day.date())
+            .is_some_and(|results| results.iter().any(|result| &result.player == player_id))
+    }
+
+    // Recorded as soon as the round starts, so leaving early still uses up the day
+    pub fn start(&mut self, day: Day, player_id: &PlayerId, username: &PlayerId) {
+        if self.has_played(day, player_id) {
+            return;
+        }
+
+        self.days.entry(day.date()).or_default().push(DailyResult {
+            player: player_id.clone(),
+            username: username.clone(),
+            score: 0,
+            words: 0,
+        });
+        self.save(&day.date());
+    }
+
+    pub fn finish(&mut self, day: Day, player_id: &PlayerId, score: u32, words: usize) {
+        let Some(result) = self.days.get_mut(&day.date()).and_then(|results| {
+            results
+                .iter_mut()
+                .find(|result| &result.player == player_id)
+        }) else {
+            return;
+        };
+
+        result.score = score;
+        result.words = words;
+        self.save(&day.date());
+    }
+
+    pub fn leaderboard(&self, day: Day) -> Vec<DailyResult> {
+        let mut results = self.days.get(&day.date()).cloned().unwrap_or_default();
+        results.sort_by_key(|result| std::cmp::Reverse((result.score, result.words)));
+        results
+    }
+}
+
+// The daily board a room was opened for
+#[derive(Debug, Clone)]
+pub struct Daily {
+    pub day: Day,
+    results: Arc<Mutex<DailyResults>>,
+}
+
+impl Daily {
+    pub fn new(day: Day, results: Arc<Mutex<DailyResults>>) -> Self {
+        Self { day, results }
+    }
+
+    // Everyone gets the same board, rolled with the default settings
+    pub fn setup(&self) -> BoardSetup {
+        GameSettings::default().roll(Some(self.day.seed()))
+    }
+
+    pub fn has_played(&self, player_id: &PlayerId) -> bool {
+        self.results
+            .lock()
+            .is_ok_and(|results| results.has_played(self.day, player_id))
+    }
+
+    pub fn start(&self, player_id: &PlayerId, username: &PlayerId) {
+        if let Ok(mut results) = self.results.lock() {
+            results.start(self.day, player_id, username);
+        }
+    }
+
+    pub fn finish(&self, player_id: &PlayerId, score: u32, words: usize) {
+        if let Ok(mut results) = self.results.lock() {
+            results.finish(self.day, player_id, score, words);
+        }
+    }
+
+    pub fn leaderboard(&self) -> Vec<DailyResult> {
+        self.results
+            .lock()
+            .map(|results| results.leaderboard(self.day))
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    // Each test gets its own directory, since saves land in it in the background
+    fn results(name: &str) -> DailyResults {
+        let dir = env::temp_dir().join(format!("daily-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        DailyResults::load(dir.to_str().unwrap())
+    }
+
+    #[test]
+    fn dates_follow_the_calendar() {
+        assert_eq!(Day(0).date(), "1970-01-01");
+        assert_eq!(Day(11_016).date(), "2000-02-29");
+        assert_eq!(Day(11_017).date(), "2000-03-01");
+        assert_eq!(Day(19_722).date(), "2023-12-31");
+        assert_eq!(Day(19_723).date(), "2024-01-01");
+    }
+
+    #[test]
+    fn starting_uses_up_the_day() {
+        let mut results = results("start");
+        let day = Day(20_000);
+        assert!(!results.has_played(day, &PlayerId::from("ann")));
+
+        results.start(day, &PlayerId::from("ann"), &PlayerId::from("Ann"));
+        assert!(results.has_played(day, &PlayerId::from("ann")));
+        assert!(!results.has_played(Day(20_001), &PlayerId::from("ann")));
+
+        // Starting again doesn't add a second result
+        results.start(day, &PlayerId::from("ann"), &PlayerId::from("Ann"));
+        assert_eq!(results.leaderboard(day).len(), 1);
+    }
+
+    #[test]
+    fn leaderboard_ranks_by_score_then_words() {
+        let mut results = results("leaderboard");
+        let day = Day(20_000);
+        for (name, score, words) in [("ann", 10, 4), ("bob", 12, 3), ("cat", 10, 6)] {
+            results.start(day, &PlayerId::from(name), &PlayerId::from(name));
+            results.finish(day, &PlayerId::from(name), score, words);
+        }
+        // Nothing is recorded for a round that never started
+        results.finish(day, &PlayerId::from("dan"), 50, 9);
+
+        let order: Vec<_> = results
+            .leaderboard(day)
+            .into_iter()
+            .map(|result| result.player.0)
+            .collect();
+        assert_eq!(order, ["bob", "cat", "ann"]);
+    }
+
+    #[test]
+    fn results_are_read_back_from_each_days_file() {
+        let dir = env::temp_dir().join(format!("daily-{}-load", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let json = r#"[{"player":"ann","username":"Ann","score":7,"words":3}]"#;
+        fs::write(dir.join("2024-01-01.json"), json).unwrap();
+
+        let results = DailyResults::load(dir.to_str().unwrap());
+        assert!(results.has_played(Day(19_723), &PlayerId::from("ann")));
+        assert_eq!(results.leaderboard(Day(19_723))[0].score, 7);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
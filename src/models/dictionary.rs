@@ -1,7 +1,12 @@
-use std::collections::HashMap;
-use std::fs::File;
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::fs::{self, File};
 use std::io::{self, BufRead};
 use std::path::Path;
+use std::sync::Arc;
+
+// Dictionary files are named "<name>-dictionary.txt" in the static files directory
+const DICTIONARY_SUFFIX: &str = "-dictionary.txt";
 
 #[derive(Default, Debug)]
 pub struct TrieNode {
@@ -29,6 +34,20 @@ impl Dictionary {
         Ok(dictionary)
     }
 
+    pub fn static_path() -> String {
+        env::var("STATIC_FILES_PATH").unwrap_or_else(|_| "/app/static".to_string())
+    }
+
+    // The dictionary shipped with the static files
+    pub fn default_path() -> String {
+        format!(
+            "{}/{}{}",
+            Self::static_path(),
+            Dictionaries::DEFAULT,
+            DICTIONARY_SUFFIX
+        )
+    }
+
     pub fn insert(&mut self, word: &str, definition: String) {
         let mut node = &mut self.root;
         for ch in word.chars() {
@@ -58,7 +77,7 @@ impl Dictionary {
 
     fn load_from_file(&mut self, file_path: &str) -> io::Result<()> {
         let path = Path::new(file_path);
-        let file = File::open(&path)?;
+        let file = File::open(path)?;
         let reader = io::BufReader::new(file);
 
         for line in reader.lines() {
@@ -79,3 +98,72 @@ impl Dictionary {
         }
     }
 }
+
+// Every dictionary a room can play with, keyed by name
+#[derive(Debug)]
+pub struct Dictionaries {
+    dictionaries: BTreeMap<String, Arc<Dictionary>>,
+}
+
+impl Dictionaries {
+    pub const DEFAULT: &'static str = "scrabble";
+
+    // Load every dictionary file in the directory, which has to include the default one
+    pub fn load(dir_path: &str) -> io::Result<Self> {
+        let mut dictionaries = BTreeMap::new();
+
+        for entry in fs::read_dir(dir_path)? {
+            let path = entry?.path();
+            let name = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_suffix(DICTIONARY_SUFFIX));
+
+            if let (Some(name), Some(file_path)) = (name, path.to_str()) {
+                println!("Loading dictionary: {}", name);
+                let dictionary = Dictionary::new(file_path)?;
+                dictionaries.insert(name.to_string(), Arc::new(dictionary));
+            }
+        }
+
+        if !dictionaries.contains_key(Self::DEFAULT) {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("Missing {}{}", Self::DEFAULT, DICTIONARY_SUFFIX),
+            ));
+        }
+
+        Ok(Self { dictionaries })
+    }
+
+    // Falls back to the default dictionary for unknown names
+    pub fn get(&self, name: &str) -> Arc<Dictionary> {
+        self.dictionaries
+            .get(name)
+            .unwrap_or_else(|| &self.dictionaries[Self::DEFAULT])
+            .clone()
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.dictionaries.contains_key(name)
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.dictionaries.keys().map(|name| name.as_str())
+    }
+}
+
+// Rooms in tests play with a handful of words instead of a dictionary file
+#[cfg(test)]
+impl Dictionaries {
+    pub fn from_words(words: &[&str]) -> Self {
+        let mut dictionary = Dictionary {
+            root: TrieNode::default(),
+        };
+        for word in words {
+            dictionary.insert(word, String::new());
+        }
+        let dictionaries = BTreeMap::from([(Self::DEFAULT.to_string(), Arc::new(dictionary))]);
+        Self { dictionaries }
+    }
+}
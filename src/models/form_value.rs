@@ -0,0 +1,38 @@
+use serde::Serialize;
+
+// Options picked from a form. The form sends back the name serde gives the
+// variant, so that name is the value, rather than a second list kept in step
+pub trait FormValue: Serialize {
+    fn value(&self) -> String {
+        match serde_json::to_value(self) {
+            Ok(serde_json::Value::String(value)) => value,
+            _ => panic!("Form options have to serialize to a string"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{BoardSize, DiceSet, Difficulty, HintKind, Scoreboard, ScoringMode};
+    use serde::de::DeserializeOwned;
+    use std::fmt::Debug;
+
+    fn round_trips<T: FormValue + DeserializeOwned + PartialEq + Debug>(options: &[T]) {
+        for option in options {
+            let value = serde_json::Value::String(option.value());
+            assert_eq!(&serde_json::from_value::<T>(value).unwrap(), option);
+        }
+    }
+
+    #[test]
+    fn values_read_back_as_the_same_option() {
+        round_trips(&BoardSize::ALL);
+        round_trips(&DiceSet::ALL);
+        round_trips(&Difficulty::ALL);
+        round_trips(&HintKind::ALL);
+        round_trips(&Scoreboard::ALL);
+        round_trips(&ScoringMode::ALL);
+        assert_eq!(DiceSet::SuperBig.value(), "superbig");
+    }
+}
@@ -0,0 +1,134 @@
+use super::form_value::FormValue;
+use super::word_list::{Cell, FoundWord, WordList};
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// What a player can ask for during a round
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HintKind {
+    Letters,
+    Cell,
+    Counts,
+}
+
+impl HintKind {
+    pub const ALL: [HintKind; 3] = [HintKind::Letters, HintKind::Cell, HintKind::Counts];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            HintKind::Letters => "First letters",
+            HintKind::Cell => "Starting cell",
+            HintKind::Counts => "Words left",
+        }
+    }
+}
+
+impl FormValue for HintKind {}
+
+// What a hint revealed, kept so it stays on screen for the rest of the round
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Hint {
+    // The start and length of a word the player hasn't found
+    Letters { start: String, length: usize },
+    // The cell a word the player hasn't found begins on
+    Cell(Cell),
+    // How many words of each length are left to find, shortest first
+    Counts(Vec<(usize, usize)>),
+}
+
+impl Hint {
+    // Hints only come from the words the player hasn't found yet, so there's
+    // nothing to give once they have them all
+    pub fn new(kind: HintKind, board_words: &WordList, found_words: &WordList) -> Option<Self> {
+        let missing: Vec<&FoundWord> = board_words
+            .iter()
+            .filter(|found| !found_words.contains(&found.word))
+            .collect();
+        let mut rng = rand::thread_rng();
+
+        match kind {
+            HintKind::Letters => missing.choose(&mut rng).map(|found| Hint::Letters {
+                start: found.word.chars().take(2).collect(),
+                length: found.word.len(),
+            }),
+            HintKind::Cell => missing
+                .choose(&mut rng)
+                .and_then(|found| found.path.first())
+                .map(|cell| Hint::Cell(*cell)),
+            HintKind::Counts => {
+                if missing.is_empty() {
+                    return None;
+                }
+                let mut counts = BTreeMap::new();
+                for found in missing {
+                    *counts.entry(found.word.len()).or_insert(0) += 1;
+                }
+                Some(Hint::Counts(counts.into_iter().collect()))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn board_words() -> WordList {
+        let mut words = WordList::new();
+        words.add("CAT", String::new(), vec![(0, 0), (0, 1), (1, 1)]);
+        words.add(
+            "HOUSE",
+            String::new(),
+            vec![(2, 0), (2, 1), (3, 1), (3, 2), (3, 3)],
+        );
+        words.add("DOG", String::new(), vec![(1, 3), (2, 3), (2, 2)]);
+        words
+    }
+
+    fn found(words: &[&str]) -> WordList {
+        let board_words = board_words();
+        let mut found = WordList::new();
+        for word in words {
+            found.add_from_board_if_not_exists(word, &board_words);
+        }
+        found
+    }
+
+    #[test]
+    fn hints_point_at_the_word_left_to_find() {
+        let found = found(&["CAT", "DOG"]);
+        assert_eq!(
+            Hint::new(HintKind::Letters, &board_words(), &found),
+            Some(Hint::Letters {
+                start: "HO".to_string(),
+                length: 5
+            })
+        );
+        assert_eq!(
+            Hint::new(HintKind::Cell, &board_words(), &found),
+            Some(Hint::Cell((2, 0)))
+        );
+    }
+
+    #[test]
+    fn counts_leave_out_found_words_shortest_first() {
+        assert_eq!(
+            Hint::new(HintKind::Counts, &board_words(), &WordList::new()),
+            Some(Hint::Counts(vec![(3, 2), (5, 1)]))
+        );
+        assert_eq!(
+            Hint::new(HintKind::Counts, &board_words(), &found(&["CAT"])),
+            Some(Hint::Counts(vec![(3, 1), (5, 1)]))
+        );
+    }
+
+    #[test]
+    fn no_hints_once_every_word_is_found() {
+        let found = found(&["CAT", "HOUSE", "DOG"]);
+        for kind in HintKind::ALL {
+            assert_eq!(Hint::new(kind, &board_words(), &found), None);
+        }
+    }
+}
@@ -1,13 +1,38 @@
 mod board;
+mod board_policy;
 mod boggle;
+mod bot;
+mod challenge;
+mod daily;
 mod dictionary;
+mod form_value;
+mod hint;
 mod player;
+mod room;
+mod scoring;
+mod settings;
+mod solver;
+mod store;
+mod submission;
+mod tile;
 mod timer;
 mod word_list;
 
-pub use board::Board;
+pub use board::{Board, BoardNotationError, BoardSetup, BoardSetupError, BoardSize, DiceSet};
+pub use board_policy::BoardPolicy;
 pub use boggle::Boggle;
-pub use dictionary::{Dictionary, SearchResult};
-pub use player::{PlayerId, PlayerIdSubmission, PlayerList};
-pub use timer::Timer;
-pub use word_list::WordList;
+pub use bot::{BotProfile, Difficulty};
+pub use challenge::{Challenge, ChallengeAttempt, ChallengeId, ChallengeResult, Challenges};
+pub use daily::{Daily, DailyResult, DailyResults, Day};
+pub use dictionary::{Dictionaries, Dictionary, SearchResult};
+pub use form_value::FormValue;
+pub use hint::{Hint, HintKind};
+pub use player::{PlayerId, PlayerIdSubmission, PlayerList, Team};
+pub use room::{RoomCode, Rooms};
+pub use scoring::{LengthRule, LetterRule, Scoring, ScoringMode, ScoringRule};
+pub use settings::{GameSettings, Scoreboard, SettingsError};
+pub use solver::Solver;
+pub use submission::SubmissionResult;
+pub use tile::Tile;
+pub use timer::{Timer, TimerEvent};
+pub use word_list::{Cell, WordList};
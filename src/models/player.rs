@@ -1,7 +1,7 @@
-use crate::models::WordList;
+use crate::models::{Difficulty, Hint, ScoringRule, WordList};
 use axum::extract::ws::Message;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 use tokio::sync::mpsc::UnboundedSender;
 
@@ -10,7 +10,7 @@ pub struct PlayerIdSubmission {
     pub username: PlayerId,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct PlayerList {
     players: HashMap<PlayerId, Player>,
 }
@@ -33,36 +33,244 @@ impl PlayerList {
             .or_insert(Player::new(sender, username));
     }
 
+    // Bots have no connection, so nothing sent to them goes anywhere
+    pub fn add_bot(&mut self, id: PlayerId, username: PlayerId, difficulty: Difficulty) {
+        let (sender, _) = tokio::sync::mpsc::unbounded_channel();
+        let mut bot = Player::new(sender, username);
+        bot.bot = Some(difficulty);
+        bot.ready = true;
+        self.players.insert(id, bot);
+    }
+
+    pub fn bots(&self) -> Vec<(&PlayerId, &Player)> {
+        self.get_active_players_sorted_by_name()
+            .into_iter()
+            .filter(|(_, player)| player.is_bot())
+            .collect()
+    }
+
     pub fn remove_inactive(&mut self) {
         self.players.retain(|_, player| player.active);
     }
 
+    // Bots don't keep a room open on their own
     pub fn all_inactive(&self) -> bool {
-        self.players.values().all(|player| !player.active)
+        self.players
+            .values()
+            .all(|player| !player.active || player.is_bot())
     }
 
     pub fn mark_inactive(&mut self, player_id: &PlayerId) {
-        if let Some(player) = self.players.get_mut(&player_id) {
+        if let Some(player) = self.players.get_mut(player_id) {
             player.mark_inactive();
         }
     }
 
-    pub fn mark_active(&mut self, player_id: &PlayerId) {
-        if let Some(player) = self.players.get_mut(&player_id) {
+    // A returning player gets the sender of their new connection
+    pub fn reconnect(&mut self, player_id: &PlayerId, sender: UnboundedSender<Message>) {
+        if let Some(player) = self.players.get_mut(player_id) {
+            player.sender = sender;
             player.mark_active();
         }
     }
 
+    pub fn remove(&mut self, player_id: &PlayerId) -> Option<Player> {
+        self.players.remove(player_id)
+    }
+
+    pub fn is_human(&self, player_id: &PlayerId) -> bool {
+        self.players
+            .get(player_id)
+            .is_some_and(|player| player.active && !player.is_bot())
+    }
+
+    pub fn is_active(&self, player_id: &PlayerId) -> bool {
+        self.players
+            .get(player_id)
+            .is_some_and(|player| player.active)
+    }
+
+    // Returns whether the player is now ready
+    pub fn toggle_ready(&mut self, player_id: &PlayerId) -> bool {
+        match self.players.get_mut(player_id) {
+            Some(player) => {
+                player.ready = !player.ready;
+                player.ready
+            }
+            None => false,
+        }
+    }
+
+    pub fn all_ready(&self) -> bool {
+        let mut active = self
+            .players
+            .values()
+            .filter(|player| player.active)
+            .peekable();
+        active.peek().is_some() && active.all(|player| player.ready)
+    }
+
     pub fn clear_state(&mut self) {
         for player in self.players.values_mut() {
             player.score = 0;
+            // Bots are always ready for the next round
+            player.ready = player.is_bot();
             player.words.clear();
+            player.hints.clear();
         }
     }
 
+    // Strike every word that more than one player found
+    pub fn cancel_shared_words(&mut self) {
+        let mut seen = HashSet::new();
+        let mut shared = HashSet::new();
+        for player in self.players.values() {
+            for found in player.words.iter() {
+                if !seen.insert(found.word.clone()) {
+                    shared.insert(found.word.clone());
+                }
+            }
+        }
+
+        for player in self.players.values_mut() {
+            player.words.cancel(&shared);
+        }
+    }
+
+    // Ordered by the points scored so far this round
+    pub fn get_players_sorted_by_round_score(&self) -> Vec<(&PlayerId, &Player)> {
+        let mut sorted_players: Vec<_> = self.players.iter().collect();
+        sorted_players
+            .sort_by_key(|(_, player)| std::cmp::Reverse((player.score, player.words.len())));
+        sorted_players
+    }
+
+    // Connected players in name order
+    pub fn get_active_players_sorted_by_name(&self) -> Vec<(&PlayerId, &Player)> {
+        let mut players: Vec<_> = self
+            .players
+            .iter()
+            .filter(|(_, player)| player.active)
+            .collect();
+        players.sort_by(|(_, a), (_, b)| a.username.0.cmp(&b.username.0));
+        players
+    }
+
+    // Adds each player's round total to their match total
+    pub fn add_round_scores(&mut self) {
+        for player in self.players.values_mut() {
+            player.match_score += player.words.total_score;
+        }
+    }
+
+    pub fn clear_match(&mut self) {
+        for player in self.players.values_mut() {
+            player.match_score = 0;
+        }
+    }
+
+    pub fn get_players_sorted_by_match_score(&self) -> Vec<(&PlayerId, &Player)> {
+        let mut sorted_players: Vec<_> = self.players.iter().collect();
+        sorted_players.sort_by_key(|(_, player)| std::cmp::Reverse(player.match_score));
+        sorted_players
+    }
+
+    // Puts the player on the team with the fewest connected players
+    pub fn assign_team(&mut self, player_id: &PlayerId, teams: u32) {
+        let mut sizes = vec![0; teams.max(1) as usize];
+        for (id, player) in &self.players {
+            if player.active && id != player_id && player.team < teams {
+                sizes[player.team as usize] += 1;
+            }
+        }
+        let smallest = (0..teams.max(1))
+            .min_by_key(|&team| sizes[team as usize])
+            .unwrap_or(0);
+        if let Some(player) = self.players.get_mut(player_id) {
+            player.team = smallest;
+        }
+    }
+
+    // Deals connected players out to the teams in name order
+    pub fn assign_teams(&mut self, teams: u32) {
+        let ids: Vec<PlayerId> = self
+            .get_active_players_sorted_by_name()
+            .into_iter()
+            .map(|(player_id, _)| player_id.clone())
+            .collect();
+        for (i, player_id) in ids.iter().enumerate() {
+            if let Some(player) = self.players.get_mut(player_id) {
+                player.team = i as u32 % teams.max(1);
+            }
+        }
+    }
+
+    pub fn switch_team(&mut self, player_id: &PlayerId, teams: u32) {
+        if let Some(player) = self.players.get_mut(player_id) {
+            player.team = (player.team + 1) % teams.max(1);
+        }
+    }
+
+    // Whether anyone on the player's team, connected or not, has found the word
+    pub fn team_has_found(&self, player_id: &PlayerId, word: &str) -> bool {
+        let Some(team) = self.players.get(player_id).map(|player| player.team) else {
+            return false;
+        };
+        self.players
+            .values()
+            .any(|player| player.team == team && player.words.contains(word))
+    }
+
+    pub fn teammates(&self, player_id: &PlayerId) -> Vec<(&PlayerId, &Player)> {
+        let Some(team) = self.players.get(player_id).map(|player| player.team) else {
+            return Vec::new();
+        };
+        self.get_active_players_sorted_by_name()
+            .into_iter()
+            .filter(|(id, player)| player.team == team && *id != player_id)
+            .collect()
+    }
+
+    // Every word found by someone on the team, each counted once
+    pub fn team_words(&self, team: u32) -> WordList {
+        let mut words = WordList::new();
+        for player in self.players.values().filter(|player| player.team == team) {
+            words.merge(&player.words);
+        }
+        words
+    }
+
+    // Teams score from their pooled words, so a word found by two teammates counts once
+    pub fn get_teams_sorted_by_score(&self, teams: u32, rule: &dyn ScoringRule) -> Vec<Team> {
+        let mut sorted_teams: Vec<Team> = (0..teams)
+            .map(|team| {
+                let mut members: Vec<&Player> = self
+                    .players
+                    .values()
+                    .filter(|player| player.team == team)
+                    .collect();
+                members.sort_by(|a, b| a.username.0.cmp(&b.username.0));
+                let words = self.team_words(team);
+                Team {
+                    number: team + 1,
+                    score: words.iter().map(|found| rule.score(&found.word)).sum(),
+                    total_score: words.iter().map(|found| found.points).sum(),
+                    words: words.len(),
+                    members: members
+                        .iter()
+                        .map(|player| player.username.clone())
+                        .collect(),
+                }
+            })
+            .filter(|team| !team.members.is_empty())
+            .collect();
+        sorted_teams.sort_by_key(|team| std::cmp::Reverse((team.total_score, team.score)));
+        sorted_teams
+    }
+
     pub fn get_players_sorted_by_score(&self) -> Vec<(&PlayerId, &Player)> {
         let mut sorted_players: Vec<_> = self.players.iter().collect();
-        sorted_players.sort_by(|a, b| b.1.words.total_score.cmp(&a.1.words.total_score));
+        sorted_players.sort_by_key(|(_, player)| std::cmp::Reverse(player.words.total_score));
         sorted_players
     }
 
@@ -74,10 +282,14 @@ impl PlayerList {
         self.players.get_mut(player_id)
     }
 
-    pub fn values_mut(&mut self) -> std::collections::hash_map::ValuesMut<PlayerId, Player> {
+    pub fn values_mut(&mut self) -> std::collections::hash_map::ValuesMut<'_, PlayerId, Player> {
         self.players.values_mut()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.players.is_empty()
+    }
+
     pub fn contains_key(&self, player_id: &PlayerId) -> bool {
         self.players.contains_key(player_id)
     }
@@ -86,29 +298,64 @@ impl PlayerList {
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
 pub struct PlayerId(pub String);
 
+impl From<&str> for PlayerId {
+    fn from(id: &str) -> Self {
+        PlayerId(id.to_string())
+    }
+}
+
 impl fmt::Display for PlayerId {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", self.0)
     }
 }
 
+// Totals for one team, numbered from 1 for display
+#[derive(Debug, Clone)]
+pub struct Team {
+    pub number: u32,
+    // Live points this round
+    pub score: u32,
+    // Points once the round is scored
+    pub total_score: u32,
+    pub words: usize,
+    pub members: Vec<PlayerId>,
+}
+
 #[derive(Debug, Clone)]
 pub struct Player {
+    // Points so far this round, before any shared words are cancelled
     pub score: u32,
+    // Points from every finished round of the current match
+    pub match_score: u32,
+    // Messages meant only for this player, like views with host controls
     pub sender: UnboundedSender<Message>,
     pub username: PlayerId,
     pub active: bool,
+    // Waiting in the lobby for the next round to start
+    pub ready: bool,
+    // Set for computer opponents
+    pub bot: Option<Difficulty>,
+    // Always 0 unless the room plays in teams
+    pub team: u32,
     pub words: WordList,
+    // Hints asked for this round
+    pub hints: Vec<Hint>,
 }
 
 impl Player {
     pub fn new(sender: UnboundedSender<Message>, username: PlayerId) -> Self {
         Self {
             score: 0,
+            match_score: 0,
             sender,
             words: WordList::new(),
             username,
             active: true,
+            ready: false,
+            bot: None,
+            team: 0,
+            hints: Vec::new(),
         }
     }
 
@@ -119,4 +366,202 @@ impl Player {
     pub fn mark_active(&mut self) {
         self.active = true;
     }
+
+    pub fn is_bot(&self) -> bool {
+        self.bot.is_some()
+    }
+
+    pub fn send(&self, html: String) {
+        if self.is_bot() {
+            return;
+        }
+
+        if let Err(e) = self.sender.send(Message::Text(html)) {
+            eprintln!("Failed to send to {}: {}", self.username, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Scoring;
+
+    fn players(names: &[&str]) -> PlayerList {
+        let mut players = PlayerList::new();
+        for name in names {
+            let (sender, _) = tokio::sync::mpsc::unbounded_channel();
+            players.add_player(PlayerId::from(*name), sender, PlayerId::from(*name));
+        }
+        players
+    }
+
+    #[test]
+    fn toggle_ready_reports_the_new_state() {
+        let mut players = players(&["ann"]);
+        assert!(players.toggle_ready(&PlayerId::from("ann")));
+        assert!(!players.toggle_ready(&PlayerId::from("ann")));
+        assert!(!players.toggle_ready(&PlayerId::from("nobody")));
+    }
+
+    #[test]
+    fn all_ready_waits_for_every_connected_player() {
+        let mut players = players(&["ann", "bob"]);
+        players.toggle_ready(&PlayerId::from("ann"));
+        assert!(!players.all_ready());
+
+        players.mark_inactive(&PlayerId::from("bob"));
+        assert!(players.all_ready());
+    }
+
+    #[test]
+    fn an_empty_room_is_not_ready() {
+        assert!(!PlayerList::new().all_ready());
+
+        let mut players = players(&["ann"]);
+        players.mark_inactive(&PlayerId::from("ann"));
+        assert!(!players.all_ready());
+    }
+
+    #[test]
+    fn bots_stay_ready_between_rounds() {
+        let mut players = players(&["ann"]);
+        players.add_bot(
+            PlayerId::from("bot"),
+            PlayerId::from("Bot"),
+            Difficulty::default(),
+        );
+        players.toggle_ready(&PlayerId::from("ann"));
+        assert!(players.all_ready());
+
+        players.clear_state();
+        assert!(!players.get(&PlayerId::from("ann")).unwrap().ready);
+        assert!(players.get(&PlayerId::from("bot")).unwrap().ready);
+    }
+
+    // Gives the player the words and scores them the classic way
+    fn find(players: &mut PlayerList, name: &str, words: &[&str]) {
+        let player = players.get_mut(&PlayerId::from(name)).unwrap();
+        player.words.merge(&WordList::from_words(words));
+        player.words.total_words(&Scoring::Classic);
+    }
+
+    #[test]
+    fn round_scores_add_up_over_a_match() {
+        let mut players = players(&["ann", "bob"]);
+        find(&mut players, "ann", &["CAT"]);
+        find(&mut players, "bob", &["HOUSE"]);
+        players.add_round_scores();
+
+        players.clear_state();
+        find(&mut players, "ann", &["TIGERS"]);
+        players.add_round_scores();
+
+        assert_eq!(
+            players.get(&PlayerId::from("ann")).unwrap().match_score,
+            1 + 3
+        );
+        assert_eq!(players.get(&PlayerId::from("bob")).unwrap().match_score, 2);
+        let order: Vec<_> = players
+            .get_players_sorted_by_match_score()
+            .into_iter()
+            .map(|(player_id, _)| player_id.0.as_str())
+            .collect();
+        assert_eq!(order, ["ann", "bob"]);
+
+        players.clear_match();
+        assert_eq!(players.get(&PlayerId::from("ann")).unwrap().match_score, 0);
+    }
+
+    #[test]
+    fn words_found_by_several_players_are_cancelled() {
+        let mut players = players(&["ann", "bob"]);
+        find(&mut players, "ann", &["CAT", "DOG"]);
+        find(&mut players, "bob", &["CAT", "HOUSE"]);
+        players.cancel_shared_words();
+        for player in players.values_mut() {
+            player.words.total_words(&Scoring::Classic);
+        }
+
+        let ann = &players.get(&PlayerId::from("ann")).unwrap().words;
+        let bob = &players.get(&PlayerId::from("bob")).unwrap().words;
+        for words in [ann, bob] {
+            let cat = words.get("CAT").unwrap();
+            assert!(cat.cancelled);
+            assert_eq!(cat.points, 0);
+        }
+        assert!(!ann.get("DOG").unwrap().cancelled);
+        assert_eq!(ann.total_score, 1);
+        assert_eq!(bob.total_score, 2);
+    }
+
+    fn team_of(players: &PlayerList, name: &str) -> u32 {
+        players.get(&PlayerId::from(name)).unwrap().team
+    }
+
+    #[test]
+    fn new_players_join_the_smallest_team() {
+        let mut players = PlayerList::new();
+        let mut join = |name: &str| {
+            let (sender, _) = tokio::sync::mpsc::unbounded_channel();
+            players.add_player(PlayerId::from(name), sender, PlayerId::from(name));
+            players.assign_team(&PlayerId::from(name), 2);
+            team_of(&players, name)
+        };
+        assert_eq!(join("ann"), 0);
+        assert_eq!(join("bob"), 1);
+        assert_eq!(join("cat"), 0);
+        assert_eq!(join("dan"), 1);
+    }
+
+    #[test]
+    fn players_who_left_do_not_count_towards_a_team() {
+        let mut players = players(&["ann", "bob", "cat"]);
+        players.assign_teams(2);
+        players.mark_inactive(&PlayerId::from("bob"));
+        players.assign_team(&PlayerId::from("cat"), 2);
+        assert_eq!(team_of(&players, "cat"), 1);
+    }
+
+    #[test]
+    fn teams_are_dealt_in_name_order() {
+        let mut players = players(&["dan", "ann", "cat", "bob"]);
+        players.assign_teams(3);
+        let teams: Vec<_> = ["ann", "bob", "cat", "dan"]
+            .iter()
+            .map(|name| team_of(&players, name))
+            .collect();
+        assert_eq!(teams, [0, 1, 2, 0]);
+
+        players.switch_team(&PlayerId::from("cat"), 3);
+        assert_eq!(team_of(&players, "cat"), 0);
+    }
+
+    #[test]
+    fn teammates_share_one_word_list() {
+        let mut players = players(&["ann", "bob", "cat"]);
+        players.assign_teams(2);
+        find(&mut players, "ann", &["CAT", "DOG"]);
+        find(&mut players, "cat", &["DOG", "HOUSE"]);
+        find(&mut players, "bob", &["TIGER"]);
+
+        let teammates: Vec<_> = players
+            .teammates(&PlayerId::from("ann"))
+            .into_iter()
+            .map(|(player_id, _)| player_id.0.as_str())
+            .collect();
+        assert_eq!(teammates, ["cat"]);
+        assert_eq!(players.team_words(0).len(), 3);
+
+        let teams = players.get_teams_sorted_by_score(2, &Scoring::Classic);
+        assert_eq!(teams[0].number, 1);
+        assert_eq!(
+            teams[0].members,
+            [PlayerId::from("ann"), PlayerId::from("cat")]
+        );
+        assert_eq!(teams[0].words, 3);
+        assert_eq!(teams[0].score, 1 + 1 + 2);
+        assert_eq!(teams[0].total_score, 1 + 1 + 2);
+        assert_eq!(teams[1].total_score, 2);
+    }
 }
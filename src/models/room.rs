@@ -0,0 +1,164 @@
+use crate::models::{
+    Boggle, ChallengeAttempt, ChallengeId, Challenges, Daily, DailyResults, Day, Dictionaries,
+    Dictionary, PlayerId,
+};
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex as StdMutex};
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+// Letters used for join codes, leaving out ones that are easy to misread (I, O)
+const CODE_LETTERS: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ";
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub struct RoomCode(pub String);
+
+impl RoomCode {
+    pub const LENGTH: usize = 4;
+
+    fn generate() -> Self {
+        let mut rng = rand::thread_rng();
+        let code = (0..Self::LENGTH)
+            .map(|_| *CODE_LETTERS.choose(&mut rng).unwrap() as char)
+            .collect();
+        RoomCode(code)
+    }
+
+    // Codes are typed by players, so they are matched case-insensitively
+    pub fn normalize(&self) -> Self {
+        RoomCode(self.0.trim().to_uppercase())
+    }
+}
+
+impl fmt::Display for RoomCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[derive(Debug)]
+pub struct Rooms {
+    rooms: HashMap<RoomCode, Arc<Mutex<Boggle>>>,
+    dictionaries: Arc<Dictionaries>,
+    daily_results: Arc<StdMutex<DailyResults>>,
+    challenges: Arc<StdMutex<Challenges>>,
+}
+
+impl Rooms {
+    // How long an empty room is kept so players can reload the page or rejoin
+    pub const EMPTY_ROOM_TIMEOUT: Duration = Duration::from_secs(60);
+
+    pub fn new() -> Arc<Mutex<Self>> {
+        let dictionaries = Arc::new(
+            Dictionaries::load(&Dictionary::static_path()).expect("Failed to load dictionaries"),
+        );
+
+        let daily_results = Arc::new(StdMutex::new(DailyResults::load(&DailyResults::dir())));
+
+        let challenges = Arc::new(StdMutex::new(Challenges::load(&Challenges::dir())));
+
+        Arc::new(Mutex::new(Self {
+            rooms: HashMap::new(),
+            dictionaries,
+            daily_results,
+            challenges,
+        }))
+    }
+
+    pub fn create(&mut self) -> RoomCode {
+        self.insert(None, None, None)
+    }
+
+    // A private room with its own board and timer that only this player can join
+    pub fn create_solo(&mut self, player_id: PlayerId) -> RoomCode {
+        self.insert(Some(player_id), None, None)
+    }
+
+    // A solo room for today's daily board
+    pub fn create_daily(&mut self, player_id: PlayerId) -> RoomCode {
+        let daily = Daily::new(Day::today(), Arc::clone(&self.daily_results));
+        self.insert(Some(player_id), Some(daily), None)
+    }
+
+    // A solo room to play a board someone sent, if the challenge exists
+    pub fn create_challenge(&mut self, player_id: PlayerId, id: ChallengeId) -> Option<RoomCode> {
+        if !self.challenges.lock().ok()?.contains(&id) {
+            return None;
+        }
+        let attempt = ChallengeAttempt::new(id, Arc::clone(&self.challenges));
+        Some(self.insert(Some(player_id), None, Some(attempt)))
+    }
+
+    // Challenges are read and saved after letting go of the room list, so
+    // sending one doesn't hold up every room lookup
+    pub fn challenges(&self) -> Arc<StdMutex<Challenges>> {
+        Arc::clone(&self.challenges)
+    }
+
+    fn insert(
+        &mut self,
+        solo_player: Option<PlayerId>,
+        daily: Option<Daily>,
+        challenge: Option<ChallengeAttempt>,
+    ) -> RoomCode {
+        self.remove_abandoned();
+
+        let code = loop {
+            let code = RoomCode::generate();
+            if !self.rooms.contains_key(&code) {
+                break code;
+            }
+        };
+
+        let boggle = Boggle::new(
+            code.clone(),
+            Arc::clone(&self.dictionaries),
+            solo_player,
+            daily,
+            challenge,
+        );
+        self.rooms.insert(code.clone(), boggle);
+        println!("Created room: {}", code);
+
+        code
+    }
+
+    pub fn get(&self, code: &RoomCode) -> Option<Arc<Mutex<Boggle>>> {
+        self.rooms.get(&code.normalize()).cloned()
+    }
+
+    pub fn contains(&self, code: &RoomCode) -> bool {
+        self.rooms.contains_key(&code.normalize())
+    }
+
+    // Remove the room once it has stayed empty for the timeout
+    pub fn schedule_removal(rooms: Arc<Mutex<Self>>, code: RoomCode) {
+        tokio::spawn(async move {
+            tokio::time::sleep(Self::EMPTY_ROOM_TIMEOUT).await;
+
+            let mut rooms = rooms.lock().await;
+            let boggle = match rooms.rooms.get(&code) {
+                Some(boggle) => Arc::clone(boggle),
+                None => return,
+            };
+
+            let mut boggle = boggle.lock().await;
+            if boggle.players.all_inactive() {
+                boggle.close().await;
+                rooms.rooms.remove(&code);
+                println!("Removed empty room: {}", code);
+            }
+        });
+    }
+
+    // Drop rooms that were created but nobody ever joined
+    fn remove_abandoned(&mut self) {
+        self.rooms.retain(|_, boggle| match boggle.try_lock() {
+            Ok(boggle) => !boggle.is_abandoned(Self::EMPTY_ROOM_TIMEOUT),
+            Err(_) => true,
+        });
+    }
+}
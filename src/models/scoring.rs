@@ -0,0 +1,241 @@
+use super::form_value::FormValue;
+use super::settings::SettingsError;
+use serde::{Deserialize, Serialize};
+
+// What happens to a word more than one player found
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScoringMode {
+    // Every player scores every word they found
+    #[default]
+    KeepShared,
+    // Classic rules: a word found by two or more players scores for nobody
+    CancelShared,
+}
+
+impl ScoringMode {
+    pub const ALL: [ScoringMode; 2] = [ScoringMode::KeepShared, ScoringMode::CancelShared];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ScoringMode::KeepShared => "Score",
+            ScoringMode::CancelShared => "Cancel",
+        }
+    }
+}
+
+impl FormValue for ScoringMode {}
+
+// Points a single word is worth
+pub trait ScoringRule {
+    fn score(&self, word: &str) -> u32;
+}
+
+// Points by word length, starting at three letters; longer words use the last entry
+#[derive(Debug, Copy, Clone)]
+pub struct LengthRule<'a> {
+    points: &'a [u32],
+}
+
+impl<'a> LengthRule<'a> {
+    const FIRST_LENGTH: usize = 3;
+
+    pub fn new(points: &'a [u32]) -> Self {
+        Self { points }
+    }
+}
+
+impl ScoringRule for LengthRule<'_> {
+    fn score(&self, word: &str) -> u32 {
+        let Some(index) = word.len().checked_sub(Self::FIRST_LENGTH) else {
+            return 0;
+        };
+        self.points
+            .get(index)
+            .or(self.points.last())
+            .copied()
+            .unwrap_or(0)
+    }
+}
+
+// Sum of a value per letter, A through Z
+#[derive(Debug, Copy, Clone)]
+pub struct LetterRule {
+    values: &'static [u32; 26],
+}
+
+impl LetterRule {
+    pub fn new(values: &'static [u32; 26]) -> Self {
+        Self { values }
+    }
+}
+
+impl ScoringRule for LetterRule {
+    fn score(&self, word: &str) -> u32 {
+        word.bytes()
+            .filter(|byte| byte.is_ascii_uppercase())
+            .map(|byte| self.values[(byte - b'A') as usize])
+            .sum()
+    }
+}
+
+const CLASSIC_POINTS: [u32; 6] = [1, 1, 2, 3, 5, 11];
+// Big Boggle doesn't count three letter words
+const BIG_BOGGLE_POINTS: [u32; 6] = [0, 1, 2, 3, 5, 11];
+
+const SCRABBLE_VALUES: [u32; 26] = [
+    1, 3, 3, 2, 1, 4, 2, 4, 1, 8, 5, 1, 3, 1, 1, 3, 10, 1, 1, 1, 1, 4, 4, 8, 4, 10,
+];
+
+// 1 + log2(frequency of E / frequency of the letter) in English text
+const RARITY_VALUES: [u32; 26] = [
+    1, 4, 3, 2, 1, 3, 3, 2, 1, 7, 5, 2, 3, 1, 1, 3, 8, 2, 2, 1, 3, 4, 3, 7, 3, 8,
+];
+
+// The scoring rule a room picked
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum Scoring {
+    #[default]
+    Classic,
+    BigBoggle,
+    Scrabble,
+    Rarity,
+    // Points per word length, starting at three letters
+    Custom(Vec<u32>),
+}
+
+impl Scoring {
+    pub const ALL: [Scoring; 5] = [
+        Scoring::Classic,
+        Scoring::BigBoggle,
+        Scoring::Scrabble,
+        Scoring::Rarity,
+        Scoring::Custom(Vec::new()),
+    ];
+
+    // Longest custom table, one entry for every word length up to 16 letters
+    pub const MAX_CUSTOM_POINTS: usize = 14;
+
+    // Unknown values fall back to classic scoring, but a custom table has to parse
+    // so the host isn't left playing with points they didn't ask for
+    pub fn from_form(value: &str, custom_points: &str) -> Result<Self, SettingsError> {
+        Ok(match value {
+            "bigboggle" => Scoring::BigBoggle,
+            "scrabble" => Scoring::Scrabble,
+            "rarity" => Scoring::Rarity,
+            "custom" => Scoring::Custom(
+                Self::parse_points(custom_points).ok_or(SettingsError::CustomPoints)?,
+            ),
+            _ => Scoring::Classic,
+        })
+    }
+
+    // A comma separated list like "1,1,2,3,5,11"
+    fn parse_points(points: &str) -> Option<Vec<u32>> {
+        let points = points
+            .split(',')
+            .map(|point| point.trim().parse().ok())
+            .collect::<Option<Vec<u32>>>()?;
+        (!points.is_empty() && points.len() <= Self::MAX_CUSTOM_POINTS).then_some(points)
+    }
+
+    // The custom table as it's written in the settings form
+    pub fn custom_points(&self) -> String {
+        let points = match self {
+            Scoring::Custom(points) => points.as_slice(),
+            _ => &CLASSIC_POINTS,
+        };
+        points
+            .iter()
+            .map(|point| point.to_string())
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Scoring::Classic => "Classic",
+            Scoring::BigBoggle => "Big Boggle",
+            Scoring::Scrabble => "Scrabble letters",
+            Scoring::Rarity => "Letter rarity",
+            Scoring::Custom(_) => "Custom",
+        }
+    }
+
+    pub fn value(&self) -> &'static str {
+        match self {
+            Scoring::Classic => "classic",
+            Scoring::BigBoggle => "bigboggle",
+            Scoring::Scrabble => "scrabble",
+            Scoring::Rarity => "rarity",
+            Scoring::Custom(_) => "custom",
+        }
+    }
+}
+
+// Scored straight from the tables, so rescoring a board allocates nothing
+impl ScoringRule for Scoring {
+    fn score(&self, word: &str) -> u32 {
+        match self {
+            Scoring::Classic => LengthRule::new(&CLASSIC_POINTS).score(word),
+            Scoring::BigBoggle => LengthRule::new(&BIG_BOGGLE_POINTS).score(word),
+            Scoring::Scrabble => LetterRule::new(&SCRABBLE_VALUES).score(word),
+            Scoring::Rarity => LetterRule::new(&RARITY_VALUES).score(word),
+            Scoring::Custom(points) => LengthRule::new(points).score(word),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn length_rule_uses_the_last_entry_for_long_words() {
+        let rule = LengthRule::new(&CLASSIC_POINTS);
+        assert_eq!(rule.score("AT"), 0);
+        assert_eq!(rule.score("CAT"), 1);
+        assert_eq!(rule.score("HOUSES"), 3);
+        assert_eq!(rule.score("CATERPILLARS"), 11);
+    }
+
+    #[test]
+    fn letter_rule_adds_up_each_letter() {
+        assert_eq!(Scoring::Scrabble.score("QUIZ"), 10 + 1 + 1 + 10);
+    }
+
+    #[test]
+    fn custom_scoring_scores_from_its_table() {
+        let scoring = Scoring::from_form("custom", "2, 4,6").unwrap();
+        assert_eq!(scoring, Scoring::Custom(vec![2, 4, 6]));
+        assert_eq!(scoring.score("CAT"), 2);
+        assert_eq!(scoring.score("HORSES"), 6);
+        assert_eq!(scoring.custom_points(), "2,4,6");
+    }
+
+    #[test]
+    fn invalid_custom_points_are_rejected() {
+        for points in ["", "1,x", "1,,2", "-1", &"1,".repeat(15)] {
+            assert!(
+                matches!(
+                    Scoring::from_form("custom", points),
+                    Err(SettingsError::CustomPoints)
+                ),
+                "{:?} was accepted",
+                points
+            );
+        }
+    }
+
+    #[test]
+    fn form_values_round_trip() {
+        let tables = Scoring::ALL
+            .into_iter()
+            .filter(|s| !matches!(s, Scoring::Custom(_)));
+        for scoring in tables.chain([Scoring::Custom(vec![1, 2])]) {
+            let parsed = Scoring::from_form(scoring.value(), &scoring.custom_points());
+            assert_eq!(parsed.unwrap(), scoring);
+        }
+        assert_eq!(Scoring::from_form("unknown", "").unwrap(), Scoring::Classic);
+    }
+}
@@ -0,0 +1,169 @@
+use super::form_value::FormValue;
+use crate::models::{
+    Board, BoardPolicy, BoardSetup, BoardSize, DiceSet, Dictionaries, Scoring, ScoringMode,
+};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+// What the room sees about the other players during a round
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Scoreboard {
+    #[default]
+    Scores,
+    WordCounts,
+    // Blind play: nothing until the game is over
+    Hidden,
+}
+
+impl Scoreboard {
+    pub const ALL: [Scoreboard; 3] = [
+        Scoreboard::Scores,
+        Scoreboard::WordCounts,
+        Scoreboard::Hidden,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Scoreboard::Scores => "Words and scores",
+            Scoreboard::WordCounts => "Word counts",
+            Scoreboard::Hidden => "Hidden",
+        }
+    }
+}
+
+impl FormValue for Scoreboard {}
+
+// Why the host's settings weren't taken
+#[derive(Debug, Clone, PartialEq)]
+pub enum SettingsError {
+    // Settings only change between rounds, and never for a daily or challenge board
+    Locked,
+    BoardPolicy,
+    // Custom scoring needs a comma separated list of points
+    CustomPoints,
+}
+
+impl fmt::Display for SettingsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SettingsError::Locked => write!(f, "Settings can't change during a game"),
+            SettingsError::BoardPolicy => {
+                write!(f, "Min words can't be more than max words")
+            }
+            SettingsError::CustomPoints => write!(
+                f,
+                "Custom points need 1 to {} whole numbers separated by commas",
+                Scoring::MAX_CUSTOM_POINTS
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SettingsError {}
+
+// Rules a room plays with, changed between games
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameSettings {
+    // Round length in seconds
+    pub duration: u32,
+    pub min_word_length: usize,
+    pub board_size: BoardSize,
+    pub dice: DiceSet,
+    pub dictionary: String,
+    pub board_policy: BoardPolicy,
+    pub scoring_mode: ScoringMode,
+    pub scoring: Scoring,
+    pub scoreboard: Scoreboard,
+    // Rounds in a match, with scores carried from one round to the next
+    pub rounds: u32,
+    // Players pool their words with their team; 1 means everyone plays alone
+    pub teams: u32,
+    // Hints each player can ask for in a round; 0 turns them off
+    pub hints: u32,
+}
+
+impl Default for GameSettings {
+    fn default() -> Self {
+        Self {
+            duration: Self::DEFAULT_DURATION,
+            min_word_length: Board::MIN_WORD_LENGTH,
+            board_size: BoardSize::default(),
+            dice: DiceSet::default(),
+            dictionary: Dictionaries::DEFAULT.to_string(),
+            board_policy: BoardPolicy::default(),
+            scoring_mode: ScoringMode::default(),
+            scoring: Scoring::default(),
+            scoreboard: Scoreboard::default(),
+            rounds: 1,
+            teams: 1,
+            hints: 0,
+        }
+    }
+}
+
+impl GameSettings {
+    pub const DEFAULT_DURATION: u32 = 180;
+    // No timer; the round runs until the host ends it
+    pub const UNTIMED: u32 = 0;
+    pub const DURATIONS: [u32; 6] = [60, 120, 180, 240, 300, Self::UNTIMED];
+    pub const MIN_WORD_LENGTHS: [usize; 2] = [3, 4];
+    pub const ROUNDS: [u32; 4] = [1, 2, 3, 5];
+    pub const TEAMS: [u32; 4] = [1, 2, 3, 4];
+    pub const HINTS: [u32; 4] = [0, 1, 3, 5];
+
+    pub fn untimed(&self) -> bool {
+        self.duration == Self::UNTIMED
+    }
+
+    pub fn team_play(&self) -> bool {
+        self.teams > 1
+    }
+
+    pub fn hints_enabled(&self) -> bool {
+        self.hints > 0
+    }
+
+    pub fn roll(&self, seed: Option<u64>) -> BoardSetup {
+        BoardSetup::Roll {
+            dice: self.dice,
+            seed,
+            policy: self.board_policy,
+        }
+    }
+
+    // Settings that can't be played are turned down rather than guessed at
+    pub fn validate(&self) -> Result<(), SettingsError> {
+        if !self.board_policy.is_valid() {
+            return Err(SettingsError::BoardPolicy);
+        }
+        Ok(())
+    }
+
+    // Replace anything the room can't play with the closest option it can
+    pub fn normalize(mut self, dictionaries: &Dictionaries) -> Self {
+        if !Self::DURATIONS.contains(&self.duration) {
+            self.duration = Self::DEFAULT_DURATION;
+        }
+        if !Self::ROUNDS.contains(&self.rounds) {
+            self.rounds = 1;
+        }
+        if !Self::TEAMS.contains(&self.teams) {
+            self.teams = 1;
+        }
+        if !Self::HINTS.contains(&self.hints) {
+            self.hints = 0;
+        }
+        if !Self::MIN_WORD_LENGTHS.contains(&self.min_word_length) {
+            self.min_word_length = Board::MIN_WORD_LENGTH;
+        }
+        // Changing the board size picks that size's dice
+        if self.dice.size() != self.board_size {
+            self.dice = self.board_size.default_dice();
+        }
+        if !dictionaries.contains(&self.dictionary) {
+            self.dictionary = Dictionaries::DEFAULT.to_string();
+        }
+        self
+    }
+}
@@ -0,0 +1,181 @@
+use super::dictionary::TrieNode;
+use crate::models::{Cell, Dictionary, Tile, WordList};
+
+// Define the neighbor offsets
+const ROW_OFFSETS: [isize; 8] = [-1, -1, -1, 0, 0, 1, 1, 1];
+const COL_OFFSETS: [isize; 8] = [-1, 0, 1, -1, 1, -1, 0, 1];
+
+// Finds every dictionary word on a board
+// Cells are numbered row by row so the visited set fits in a u64 bitmask,
+// and the trie is walked one tile at a time instead of searching from the root.
+pub struct Solver<'a> {
+    root: &'a TrieNode,
+    dimension: usize,
+    // Lowercase letters of each cell, matching the keys of the trie
+    keys: Vec<Vec<char>>,
+    // Uppercase letters of each cell, used to build the found words
+    letters: Vec<&'a str>,
+    neighbors: Vec<Vec<usize>>,
+    words: WordList,
+}
+
+impl<'a> Solver<'a> {
+    pub fn new(dictionary: &'a Dictionary, board: &'a [Vec<Tile>]) -> Self {
+        let dimension = board.len();
+        let tiles = board.iter().flatten();
+
+        Self {
+            root: &dictionary.root,
+            dimension,
+            keys: tiles
+                .clone()
+                .map(|tile| tile.letters().to_lowercase().chars().collect())
+                .collect(),
+            letters: tiles.map(|tile| tile.letters()).collect(),
+            neighbors: Self::neighbors(dimension),
+            words: WordList::new(),
+        }
+    }
+
+    // Indexes of the adjacent cells for every cell on the board
+    pub fn neighbors(dimension: usize) -> Vec<Vec<usize>> {
+        let size = dimension as isize;
+        (0..dimension * dimension)
+            .map(|cell| {
+                let (i, j) = ((cell / dimension) as isize, (cell % dimension) as isize);
+                (0..8)
+                    .map(|k| (i + ROW_OFFSETS[k], j + COL_OFFSETS[k]))
+                    .filter(|&(new_i, new_j)| {
+                        new_i >= 0 && new_i < size && new_j >= 0 && new_j < size
+                    })
+                    .map(|(new_i, new_j)| (new_i * size + new_j) as usize)
+                    .collect()
+            })
+            .collect()
+    }
+
+    pub fn solve(mut self) -> WordList {
+        let mut current_word = String::new();
+        let mut path = Vec::new();
+
+        for cell in 0..self.keys.len() {
+            self.dfs(cell, self.root, 0, &mut current_word, &mut path);
+        }
+
+        self.words
+    }
+
+    fn dfs(
+        &mut self,
+        cell: usize,
+        node: &'a TrieNode,
+        visited: u64,
+        current_word: &mut String,
+        path: &mut Vec<Cell>,
+    ) {
+        // Follow every letter of the tile, so "Qu" moves two steps down the trie
+        let mut node = node;
+        for k in 0..self.keys[cell].len() {
+            match node.children.get(&self.keys[cell][k]) {
+                Some(child) => node = child,
+                None => return,
+            }
+        }
+
+        let visited = visited | (1 << cell);
+        let word_length = current_word.len();
+        current_word.push_str(self.letters[cell]);
+        path.push((cell / self.dimension, cell % self.dimension));
+
+        if node.is_end_of_word && !self.words.contains(current_word) {
+            if let Some(definition) = &node.definition {
+                self.words
+                    .add(current_word, definition.clone(), path.clone());
+            }
+        }
+
+        // Only keep going while the word is still a valid prefix
+        if !node.children.is_empty() {
+            for k in 0..self.neighbors[cell].len() {
+                let next = self.neighbors[cell][k];
+                if visited & (1 << next) == 0 {
+                    self.dfs(next, node, visited, current_word, path);
+                }
+            }
+        }
+
+        // Backtrack
+        path.pop();
+        current_word.truncate(word_length);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dictionary(words: &[&str]) -> Dictionary {
+        let mut dictionary = Dictionary {
+            root: TrieNode::default(),
+        };
+        for word in words {
+            dictionary.insert(word, format!("definition of {}", word));
+        }
+        dictionary
+    }
+
+    fn board(rows: &[&[&str]]) -> Vec<Vec<Tile>> {
+        rows.iter()
+            .map(|row| row.iter().map(|face| Tile::new(face)).collect())
+            .collect()
+    }
+
+    fn solve(words: &[&str], rows: &[&[&str]]) -> WordList {
+        let dictionary = dictionary(words);
+        let board = board(rows);
+        Solver::new(&dictionary, &board).solve()
+    }
+
+    #[test]
+    fn finds_words_through_neighbors() {
+        let words = solve(&["cat", "act", "tac"], &[&["C", "A"], &["X", "T"]]);
+        let found = words.get("CAT").expect("CAT is on the board");
+        assert_eq!(found.path, vec![(0, 0), (0, 1), (1, 1)]);
+        assert_eq!(found.definition, "definition of cat");
+        assert!(words.contains("ACT"));
+        assert!(words.contains("TAC"));
+    }
+
+    #[test]
+    fn follows_every_letter_of_a_face() {
+        let words = solve(&["quit", "the", "qit"], &[&["Qu", "I"], &["T", "He"]]);
+        assert_eq!(
+            words.get("QUIT").map(|found| found.path.clone()),
+            Some(vec![(0, 0), (0, 1), (1, 0)])
+        );
+        assert_eq!(
+            words.get("THE").map(|found| found.path.clone()),
+            Some(vec![(1, 0), (1, 1)])
+        );
+        assert!(!words.contains("QIT"));
+    }
+
+    #[test]
+    fn does_not_reuse_a_cell() {
+        let words = solve(&["tot", "tat"], &[&["T", "O"], &["X", "A"]]);
+        assert!(!words.contains("TOT"));
+        assert!(!words.contains("TAT"));
+    }
+
+    #[test]
+    fn adds_each_word_once() {
+        let words = solve(&["see"], &[&["S", "E"], &["E", "E"]]);
+        assert_eq!(words.len(), 1);
+    }
+
+    #[test]
+    fn ignores_words_not_on_the_board() {
+        let words = solve(&["cats"], &[&["C", "A"], &["X", "T"]]);
+        assert!(words.is_empty());
+    }
+}
@@ -0,0 +1,150 @@
+use serde::{de::DeserializeOwned, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread;
+
+// What the writer thread does to an entry's file, None removes it
+type Write = (String, Option<String>);
+
+// A directory under the data directory with one small JSON file per entry, so
+// changing an entry only rewrites its own file. Writes happen on a thread of the
+// store's own so handlers never wait on the disk.
+#[derive(Debug)]
+pub struct Store {
+    dir: PathBuf,
+    writer: mpsc::Sender<Write>,
+}
+
+impl Store {
+    pub fn new(dir: PathBuf) -> Self {
+        let (writer, writes) = mpsc::channel::<Write>();
+        let thread_dir = dir.clone();
+        thread::spawn(move || {
+            while let Ok(next) = writes.recv() {
+                // Only the newest of the writes waiting for each entry is worth doing
+                let mut pending = HashMap::from([next]);
+                while let Ok((key, json)) = writes.try_recv() {
+                    pending.insert(key, json);
+                }
+                for (key, json) in pending {
+                    let path = entry_path(&thread_dir, &key);
+                    let result = match json {
+                        Some(json) => write(&path, &json),
+                        None => remove(&path),
+                    };
+                    if let Err(e) = result {
+                        eprintln!("Failed to save {}: {}", path.display(), e);
+                    }
+                }
+            }
+        });
+
+        Self { dir, writer }
+    }
+
+    // Every entry that can be read, keyed by its file name; none when there's no directory yet
+    pub fn load<T: DeserializeOwned>(&self) -> HashMap<String, T> {
+        let Ok(files) = fs::read_dir(&self.dir) else {
+            return HashMap::new();
+        };
+
+        files
+            .filter_map(|file| file.ok().map(|file| file.path()))
+            .filter(|path| {
+                path.extension()
+                    .is_some_and(|extension| extension == "json")
+            })
+            .filter_map(|path| {
+                let key = path.file_stem()?.to_str()?.to_string();
+                let json = fs::read_to_string(&path).ok()?;
+                match serde_json::from_str(&json) {
+                    Ok(value) => Some((key, value)),
+                    Err(e) => {
+                        eprintln!("Failed to parse {}: {}", path.display(), e);
+                        None
+                    }
+                }
+            })
+            .collect()
+    }
+
+    pub fn save<T: Serialize>(&self, key: &str, value: &T) {
+        match serde_json::to_string(value) {
+            Ok(json) => self.send(key, Some(json)),
+            Err(e) => eprintln!("Failed to serialize {}: {}", key, e),
+        }
+    }
+
+    pub fn remove(&self, key: &str) {
+        self.send(key, None);
+    }
+
+    fn send(&self, key: &str, json: Option<String>) {
+        if self.writer.send((key.to_string(), json)).is_err() {
+            eprintln!("Writer for {} has stopped", self.dir.display());
+        }
+    }
+}
+
+// Keys are generated ids and dates, so they're safe to use as file names
+fn entry_path(dir: &Path, key: &str) -> PathBuf {
+    dir.join(format!("{}.json", key))
+}
+
+// Writes to a temporary file first so a crash can't leave half a file behind
+fn write(path: &Path, json: &str) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, json)?;
+    fs::rename(tmp, path)
+}
+
+fn remove(path: &Path) -> std::io::Result<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::time::{Duration, Instant};
+
+    // Writes land in the background, so wait a little for the directory to catch up
+    fn settled(store: &Store, expected: &[(&str, u32)]) -> bool {
+        let expected: HashMap<String, u32> = expected
+            .iter()
+            .map(|(key, value)| (key.to_string(), *value))
+            .collect();
+        let start = Instant::now();
+        while start.elapsed() < Duration::from_secs(5) {
+            if store.load::<u32>() == expected {
+                return true;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        false
+    }
+
+    #[test]
+    fn each_entry_has_its_own_file() {
+        let dir = env::temp_dir().join(format!("store-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let store = Store::new(dir.clone());
+        store.save("one", &1);
+        store.save("two", &1);
+        store.save("two", &2);
+        assert!(settled(&store, &[("one", 1), ("two", 2)]));
+
+        store.remove("one");
+        assert!(settled(&store, &[("two", 2)]));
+        assert!(!dir.join("one.json").exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,50 @@
+// Why a submitted word was or wasn't added to the player's list
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubmissionResult {
+    Accepted,
+    AlreadyFound,
+    NotInDictionary,
+    NotOnBoard,
+    // Holds the room's minimum word length
+    TooShort(usize),
+    // Not a single word of letters, or longer than any board allows
+    Malformed,
+    GameNotRunning,
+    // Spectators and people who never joined have no word list
+    NotAPlayer,
+}
+
+impl SubmissionResult {
+    pub fn is_accepted(&self) -> bool {
+        *self == SubmissionResult::Accepted
+    }
+
+    pub fn message(&self) -> String {
+        match self {
+            SubmissionResult::Accepted => "Accepted".to_string(),
+            SubmissionResult::AlreadyFound => "Already found".to_string(),
+            SubmissionResult::NotInDictionary => "Not in the dictionary".to_string(),
+            SubmissionResult::NotOnBoard => "Not on the board".to_string(),
+            SubmissionResult::TooShort(min) => format!("Words need {} or more letters", min),
+            SubmissionResult::Malformed => "Letters only, one word".to_string(),
+            SubmissionResult::GameNotRunning => "The game isn't running".to_string(),
+            SubmissionResult::NotAPlayer => {
+                "Only players in the round can submit words".to_string()
+            }
+        }
+    }
+
+    // Class the toast is styled with
+    pub fn value(&self) -> &'static str {
+        match self {
+            SubmissionResult::Accepted => "accepted",
+            SubmissionResult::AlreadyFound => "already-found",
+            SubmissionResult::NotInDictionary => "not-in-dictionary",
+            SubmissionResult::NotOnBoard => "not-on-board",
+            SubmissionResult::TooShort(_) => "too-short",
+            SubmissionResult::Malformed => "malformed",
+            SubmissionResult::GameNotRunning => "game-not-running",
+            SubmissionResult::NotAPlayer => "not-a-player",
+        }
+    }
+}
@@ -0,0 +1,48 @@
+use std::fmt;
+
+// A single die face on the board, holding one or more letters ("A", "Qu", "Th")
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tile {
+    face: String,
+    letters: String,
+}
+
+impl Tile {
+    pub fn new(face: &str) -> Self {
+        let mut chars = face.chars();
+        let face: String = match chars.next() {
+            Some(first) => first
+                .to_uppercase()
+                .chain(chars.flat_map(|c| c.to_lowercase()))
+                .collect(),
+            None => String::new(),
+        };
+        let letters = face.to_uppercase();
+
+        Self { face, letters }
+    }
+
+    // Split a die into its faces, where a lowercase letter continues the face before it
+    // e.g. "HIMNQuU" has the faces H, I, M, N, Qu and U
+    pub fn faces(die: &str) -> Vec<Tile> {
+        let mut faces: Vec<String> = Vec::new();
+        for ch in die.chars() {
+            match faces.last_mut() {
+                Some(face) if ch.is_lowercase() => face.push(ch),
+                _ => faces.push(ch.to_string()),
+            }
+        }
+        faces.iter().map(|face| Tile::new(face)).collect()
+    }
+
+    // The letters this tile adds to a word, in uppercase
+    pub fn letters(&self) -> &str {
+        &self.letters
+    }
+}
+
+impl fmt::Display for Tile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.face)
+    }
+}
@@ -1,62 +1,171 @@
-use super::boggle::{Boggle, BoggleStateEnum};
+use super::boggle::BoggleStateEnum;
+use super::settings::GameSettings;
 use crate::render::Render;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex as StdMutex};
 use std::time::Duration;
-use tokio::sync::{broadcast, Mutex, Notify};
+use tokio::sync::{broadcast, watch, Mutex};
+use tokio::time::Instant;
+
+// Sent when a count reaches zero; the run tells the game which count it came from
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct TimerEvent {
+    pub run: u64,
+    pub state: BoggleStateEnum,
+}
+
+// When the running count ends, or what was left of it once it stopped
+#[derive(Debug, Copy, Clone)]
+enum Clock {
+    Running(Instant),
+    Stopped(Duration),
+}
+
+impl Clock {
+    fn remaining(&self) -> Duration {
+        match self {
+            Clock::Running(deadline) => deadline.saturating_duration_since(Instant::now()),
+            Clock::Stopped(remaining) => *remaining,
+        }
+    }
+}
 
 #[derive(Debug)]
 pub struct Timer {
     duration: u32,
-    cancel_token: Arc<Notify>,
+    // Kept to the instant rather than whole seconds so pausing doesn't round time away
+    clock: StdMutex<Clock>,
+    // Bumped by every count and every cancel, so an event sent just before a
+    // cancel can be told apart from the current count's. Each count watches it
+    // and stops as soon as it moves on, so no cancel is left over for the next
+    run: watch::Sender<u64>,
     tx: broadcast::Sender<String>,
-    boggle_channel_tx: broadcast::Sender<BoggleStateEnum>,
+    boggle_channel_tx: broadcast::Sender<TimerEvent>,
 }
 
 impl Timer {
     pub fn new(
         tx: broadcast::Sender<String>,
-        boggle_channel_tx: broadcast::Sender<BoggleStateEnum>,
+        boggle_channel_tx: broadcast::Sender<TimerEvent>,
     ) -> Arc<Mutex<Self>> {
-        let cancel_token = Arc::new(Notify::new());
         Arc::new(Mutex::new(Self {
-            duration: Boggle::GAME_DURATION,
-            cancel_token,
+            duration: GameSettings::DEFAULT_DURATION,
+            clock: StdMutex::new(Clock::Stopped(Duration::ZERO)),
+            run: watch::Sender::new(0),
             tx,
             boggle_channel_tx,
         }))
     }
 
+    pub fn set_duration(&mut self, duration: u32) {
+        self.duration = duration;
+    }
+
+    // Untimed rounds have nothing to count
     pub async fn start(&self) {
+        if self.duration == GameSettings::UNTIMED {
+            return;
+        }
+        self.run(
+            Duration::from_secs(self.duration.into()),
+            |remaining| Render::timer(&Timer::format_time(remaining)),
+            BoggleStateEnum::GameOver,
+        );
+    }
+
+    // Picks the round back up where it was paused
+    pub async fn resume(&self) {
+        if self.duration == GameSettings::UNTIMED {
+            return;
+        }
+        let remaining = self.clock().remaining();
+        self.run(
+            remaining,
+            |remaining| Render::timer(&Timer::format_time(remaining)),
+            BoggleStateEnum::GameOver,
+        );
+    }
+
+    pub fn pause(&self) {
+        self.cancel();
+    }
+
+    // Whole seconds left, counting a started second as one still to play
+    pub fn remaining(&self) -> u32 {
+        Self::whole_seconds(self.clock().remaining())
+    }
+
+    fn whole_seconds(remaining: Duration) -> u32 {
+        let seconds = remaining.as_secs() + u64::from(remaining.subsec_nanos() > 0);
+        seconds.try_into().unwrap_or(u32::MAX)
+    }
+
+    fn clock(&self) -> Clock {
+        *self.clock.lock().expect("Timer clock poisoned")
+    }
+
+    fn set_clock(&self, clock: Clock) {
+        *self.clock.lock().expect("Timer clock poisoned") = clock;
+    }
+
+    // Whether the event came from the count that is running now
+    pub fn is_current(&self, event: &TimerEvent) -> bool {
+        *self.run.borrow() == event.run
+    }
+
+    // Counts down to the start of a round
+    pub async fn countdown(&self, seconds: u32) {
+        self.run(
+            Duration::from_secs(seconds.into()),
+            |remaining| Render::countdown(&remaining.to_string()),
+            BoggleStateEnum::InProgress,
+        );
+    }
+
+    // Broadcasts as each second passes and moves the game to the next state at zero
+    fn run(&self, duration: Duration, render: fn(u32) -> String, next_state: BoggleStateEnum) {
         let timer_tx = self.tx.clone();
-        let cancel_token = Arc::clone(&self.cancel_token);
         let boggle_channel_tx = self.boggle_channel_tx.clone();
-        let duration = self.duration;
+        let deadline = Instant::now() + duration;
+        self.set_clock(Clock::Running(deadline));
+        self.run.send_modify(|run| *run += 1);
+        let mut run = self.run.subscribe();
+        let event = TimerEvent {
+            run: *run.borrow_and_update(),
+            state: next_state,
+        };
 
-        tokio::spawn(async move {
-            let mut remaining = duration;
+        // A round paused on its last tick has nothing left, so it ends on resume
+        if duration.is_zero() {
+            if let Err(e) = boggle_channel_tx.send(event) {
+                eprintln!("Failed to send {:?} message: {}", next_state, e);
+            }
+            return;
+        }
 
-            while remaining > 0 {
+        tokio::spawn(async move {
+            loop {
+                // Wake on the next whole second left, which is part way through
+                // one when the count was resumed
+                let remaining =
+                    Self::whole_seconds(deadline.saturating_duration_since(Instant::now()));
+                let next = Duration::from_secs(u64::from(remaining.saturating_sub(1)));
                 tokio::select! {
-                    _ = tokio::time::sleep(Duration::from_secs(1)) => {
-                        remaining -= 1;
-
-                        let formated_time = Timer::format_time(remaining);
-                        let timer_html = Render::timer(&formated_time);
-
-                        if let Err(e) = timer_tx.send(timer_html) {
-                            eprintln!("Failed to send timer update: {}", e);
-                        }
-
-                        if remaining == 0 {
-                            if let Err(e) = boggle_channel_tx.send(BoggleStateEnum::GameOver) {
-                                eprintln!("Failed to send game over message: {}", e);
-                            }
-                        }
-                    },
-                    _ = cancel_token.notified() => {
+                    _ = tokio::time::sleep_until(deadline - next) => {},
+                    _ = run.changed() => {
                         break;
                     }
                 }
+
+                let remaining =
+                    Self::whole_seconds(deadline.saturating_duration_since(Instant::now()));
+                if remaining == 0 {
+                    if let Err(e) = boggle_channel_tx.send(event) {
+                        eprintln!("Failed to send {:?} message: {}", next_state, e);
+                    }
+                    break;
+                } else if let Err(e) = timer_tx.send(render(remaining)) {
+                    eprintln!("Failed to send timer update: {}", e);
+                }
             }
         });
     }
@@ -68,6 +177,118 @@ impl Timer {
     }
 
     pub fn cancel(&self) {
-        self.cancel_token.notify_one();
+        self.set_clock(Clock::Stopped(self.clock().remaining()));
+        self.run.send_modify(|run| *run += 1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timer(duration: u32) -> (Timer, broadcast::Receiver<TimerEvent>) {
+        let (tx, _) = broadcast::channel(16);
+        let (boggle_channel_tx, events) = broadcast::channel(16);
+        let timer = Timer::new(tx, boggle_channel_tx);
+        let mut timer = Arc::try_unwrap(timer).unwrap().into_inner();
+        timer.set_duration(duration);
+        (timer, events)
+    }
+
+    // Lets the count's task catch up before the test looks at what it sent
+    async fn advance(seconds: f64) {
+        tokio::time::sleep(Duration::from_secs_f64(seconds)).await;
+        for _ in 0..10 {
+            tokio::task::yield_now().await;
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn countdown_starts_the_round() {
+        let (timer, mut events) = timer(60);
+        timer.countdown(3).await;
+        advance(2.5).await;
+        assert!(events.try_recv().is_err());
+
+        advance(0.5).await;
+        let event = events.try_recv().unwrap();
+        assert_eq!(event.state, BoggleStateEnum::InProgress);
+        assert!(timer.is_current(&event));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn untimed_rounds_never_end_on_their_own() {
+        let (timer, mut events) = timer(GameSettings::UNTIMED);
+        timer.start().await;
+        advance(3600.0).await;
+        assert!(events.try_recv().is_err());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn pausing_keeps_the_part_second_left() {
+        let (timer, mut events) = timer(10);
+        timer.start().await;
+        advance(7.5).await;
+        timer.pause();
+        assert_eq!(timer.remaining(), 3);
+
+        advance(60.0).await;
+        assert!(events.try_recv().is_err());
+        assert_eq!(timer.remaining(), 3);
+
+        timer.resume().await;
+        advance(2.4).await;
+        assert!(events.try_recv().is_err());
+        advance(0.1).await;
+        let event = events.try_recv().unwrap();
+        assert_eq!(event.state, BoggleStateEnum::GameOver);
+        assert!(timer.is_current(&event));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn cancelled_counts_are_stale() {
+        let (timer, mut events) = timer(10);
+        timer.start().await;
+        advance(10.0).await;
+        let event = events.try_recv().unwrap();
+        timer.cancel();
+        assert!(!timer.is_current(&event));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn a_cancel_does_not_stop_the_next_count() {
+        let (timer, mut events) = timer(10);
+        timer.start().await;
+        advance(1.0).await;
+        timer.pause();
+        timer.cancel();
+
+        timer.countdown(3).await;
+        advance(3.0).await;
+        assert_eq!(
+            events.try_recv().unwrap().state,
+            BoggleStateEnum::InProgress
+        );
+    }
+
+    // Pausing and resuming before the first count's task has run leaves one count going
+    #[tokio::test(start_paused = true)]
+    async fn quick_pauses_leave_a_single_count() {
+        let (timer, mut events) = timer(10);
+        let mut ticks = timer.tx.subscribe();
+        timer.start().await;
+        for _ in 0..2 {
+            timer.pause();
+            timer.resume().await;
+        }
+
+        advance(1.0).await;
+        assert_eq!(ticks.try_recv().unwrap(), Render::timer("0:09"));
+        assert!(ticks.try_recv().is_err());
+
+        advance(9.0).await;
+        let event = events.try_recv().unwrap();
+        assert_eq!(event.state, BoggleStateEnum::GameOver);
+        assert!(timer.is_current(&event));
     }
 }
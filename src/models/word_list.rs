@@ -1,8 +1,26 @@
-use super::board::Board;
+use super::scoring::ScoringRule;
+use std::collections::{HashMap, HashSet};
+
+// A row and column on the board
+pub type Cell = (usize, usize);
 
 #[derive(Debug, Clone)]
+pub struct FoundWord {
+    pub word: String,
+    pub definition: String,
+    // One sequence of cells that spells the word on the board
+    pub path: Vec<Cell>,
+    // Set when another player found the same word and it no longer scores
+    pub cancelled: bool,
+    // What the word scored under the room's scoring rule
+    pub points: u32,
+}
+
+#[derive(Debug, Clone, Default)]
 pub struct WordList {
-    words: Vec<(String, String)>,
+    words: Vec<FoundWord>,
+    // Position of each word in the list, since every submission looks words up
+    index: HashMap<String, usize>,
     pub total_score: u32,
 }
 
@@ -10,38 +28,156 @@ impl WordList {
     pub fn new() -> Self {
         WordList {
             words: Vec::new(),
+            index: HashMap::new(),
             total_score: 0,
         }
     }
 
-    pub fn add(&mut self, word: &String, definition: String) {
-        self.words.push((word.to_string(), definition));
+    pub fn add(&mut self, word: &str, definition: String, path: Vec<Cell>) {
+        self.push(FoundWord {
+            word: word.to_string(),
+            definition,
+            path,
+            cancelled: false,
+            points: 0,
+        });
+    }
+
+    fn push(&mut self, found: FoundWord) {
+        self.index.insert(found.word.clone(), self.words.len());
+        self.words.push(found);
     }
 
     pub fn contains(&self, word: &str) -> bool {
-        self.words.iter().any(|(w, _)| w == word)
+        self.index.contains_key(word)
     }
 
-    pub fn add_from_board_if_not_exists(&mut self, word: &str, board_words: &WordList) {
-        if !self.contains(word) {
-            if let Some((_, definition)) = board_words.words.iter().find(|(w, _)| w == word) {
-                self.add(&word.to_string(), definition.clone());
+    pub fn get(&self, word: &str) -> Option<&FoundWord> {
+        self.index.get(word).map(|&position| &self.words[position])
+    }
+
+    // Returns whether the word was added
+    pub fn add_from_board_if_not_exists(&mut self, word: &str, board_words: &WordList) -> bool {
+        if self.contains(word) {
+            return false;
+        }
+        match board_words.get(word) {
+            Some(found) => {
+                self.push(found.clone());
+                true
             }
+            None => false,
+        }
+    }
+
+    // Adds the words from the other list that aren't in this one yet
+    pub fn merge(&mut self, other: &WordList) {
+        for found in other.iter() {
+            if !self.contains(&found.word) {
+                self.push(found.clone());
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    // Number of letters in the longest word
+    pub fn longest_word(&self) -> usize {
+        self.words
+            .iter()
+            .map(|found| found.word.len())
+            .max()
+            .unwrap_or(0)
+    }
+
+    pub fn remove_shorter_than(&mut self, length: usize) {
+        self.words.retain(|found| found.word.len() >= length);
+        self.index = self
+            .words
+            .iter()
+            .enumerate()
+            .map(|(position, found)| (found.word.clone(), position))
+            .collect();
+    }
+
+    pub fn cancel(&mut self, words: &HashSet<String>) {
+        for found in &mut self.words {
+            found.cancelled = words.contains(&found.word);
         }
     }
 
     pub fn clear(&mut self) {
         self.words.clear();
+        self.index.clear();
         self.total_score = 0;
     }
 
-    pub fn total_words(&mut self) {
-        for (word, _) in &self.words {
-            self.total_score += Board::calculate_score(word.len());
+    pub fn total_words(&mut self, rule: &dyn ScoringRule) {
+        self.total_score = 0;
+        for found in &mut self.words {
+            found.points = if found.cancelled {
+                0
+            } else {
+                rule.score(&found.word)
+            };
+            self.total_score += found.points;
         }
     }
 
-    pub fn iter(&self) -> impl Iterator<Item = &(String, String)> {
+    pub fn iter(&self) -> impl Iterator<Item = &FoundWord> {
         self.words.iter()
     }
 }
+
+// Words with no definition or path, for tests that only need the words
+#[cfg(test)]
+impl WordList {
+    pub fn from_words(words: &[&str]) -> Self {
+        let mut list = WordList::new();
+        for word in words {
+            list.add(word, String::new(), Vec::new());
+        }
+        list
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lookups_follow_removals() {
+        let mut words = WordList::from_words(&["CAT", "HOUSE", "DOG"]);
+        words.remove_shorter_than(4);
+        assert!(!words.contains("CAT"));
+        assert_eq!(
+            words.get("HOUSE").map(|found| found.word.as_str()),
+            Some("HOUSE")
+        );
+        assert_eq!(words.len(), 1);
+    }
+
+    #[test]
+    fn merge_skips_words_already_found() {
+        let mut words = WordList::from_words(&["CAT"]);
+        words.merge(&WordList::from_words(&["CAT", "DOG"]));
+        assert_eq!(words.len(), 2);
+        assert!(words.contains("DOG"));
+    }
+
+    #[test]
+    fn board_words_are_only_added_once() {
+        let board_words = WordList::from_words(&["CAT"]);
+        let mut words = WordList::new();
+        assert!(words.add_from_board_if_not_exists("CAT", &board_words));
+        assert!(!words.add_from_board_if_not_exists("CAT", &board_words));
+        assert!(!words.add_from_board_if_not_exists("DOG", &board_words));
+        assert_eq!(words.len(), 1);
+    }
+}
@@ -1,4 +1,8 @@
-use crate::models::{Board, PlayerList, WordList};
+use crate::models::{
+    Board, BoardPolicy, BoardSize, Cell, Challenge, ChallengeId, ChallengeResult, DailyResult,
+    DiceSet, Dictionaries, Difficulty, FormValue, GameSettings, Hint, HintKind, PlayerId,
+    PlayerList, RoomCode, Scoreboard, Scoring, ScoringMode, SubmissionResult, Timer, WordList,
+};
 use maud::{html, PreEscaped};
 
 pub struct Render {}
@@ -13,10 +17,64 @@ impl Render {
         .into_string()
     }
 
-    pub fn reconnect() -> String {
+    // Path of a route that belongs to a room
+    fn room_route(code: &RoomCode, route: &str) -> String {
+        format!("/room/{}/{}", code, route)
+    }
+
+    pub fn countdown(remaining: &str) -> String {
+        html! {
+            div id="game-timer" class="countdown" {
+                (remaining)
+            }
+        }
+        .into_string()
+    }
+
+    pub fn countdown_state(remaining: &str) -> String {
+        html! {
+            (PreEscaped(Self::countdown(remaining)))
+            div id="scoreboard" {}
+            div id="round-controls" {}
+            div id="game-board" {}
+            div id="word-input" {}
+            div id="hints" {}
+            div id="valid-words" {}
+        }
+        .into_string()
+    }
+
+    // Replaces the game, and its websocket, for a player the host removed
+    pub fn removed(banned: bool) -> String {
+        html! {
+            div id="game-container" {
+                div id="join-error" {
+                    @if banned {
+                        "You were banned from this room"
+                    } @else {
+                        "You were removed from this room"
+                    }
+                }
+                a href="/" { "Back" }
+            }
+        }
+        .into_string()
+    }
+
+    pub fn private_room() -> String {
+        html! {
+            div id="game-container" {
+                div id="join-error" { "This is someone's solo game" }
+                a href="/" { "Back" }
+            }
+        }
+        .into_string()
+    }
+
+    pub fn reconnect(code: &RoomCode) -> String {
         html! {
             div id="main-container" {
-                a id="reconnect" href="/" {
+                a id="reconnect" href=(format!("/room/{}", code)) {
                     "Reconnect"
                 }
             }
@@ -24,25 +82,477 @@ impl Render {
         .into_string()
     }
 
-    fn new_game_button() -> String {
+    fn new_game_button(code: &RoomCode) -> String {
+        html! {
+            form hx-post=(Self::room_route(code, "new_game")) hx-target="#setup-error" hx-swap="outerHTML" {
+                input type="text"
+                name="seed"
+                placeholder="Seed (optional)"
+                title="Enter a seed to replay a board; leave empty for a random board."
+                pattern="[0-9]{1,20}"
+                maxlength="20"
+                {}
+                input type="text"
+                name="board"
+                placeholder="Board (optional)"
+                title="Paste a board like ABCD/EFGH/IJKL/MNQuP to play it instead of rolling one."
+                pattern="[A-Za-z/]{16,80}"
+                maxlength="80"
+                {}
+                button type="submit" { "Start now" }
+            }
+        }
+        .into_string()
+    }
+
+    // Connected players and whether they're ready; the host can manage the others
+    fn lobby(
+        code: &RoomCode,
+        players: &PlayerList,
+        teams: u32,
+        host: Option<&PlayerId>,
+        viewer: &PlayerId,
+    ) -> String {
+        let viewer_is_host = host == Some(viewer);
+        html! {
+            div id="lobby" {
+                @for (player_id, player) in players.get_active_players_sorted_by_name() {
+                    div.lobby-player.ready[player.ready] {
+                        (player.username)
+                        @if teams > 1 { " [Team " (player.team + 1) "]" }
+                        @if host == Some(player_id) { " (host)" }
+                        @if player.ready { " ✓" }
+                        @if viewer_is_host && player.is_bot() {
+                            (PreEscaped(Self::host_action(code, "kick", player_id, "Remove", false)))
+                        } @else if viewer_is_host && player_id != viewer {
+                            (PreEscaped(Self::host_action(code, "host", player_id, "Make host", false)))
+                            (PreEscaped(Self::host_action(code, "kick", player_id, "Kick", false)))
+                            (PreEscaped(Self::host_action(code, "kick", player_id, "Ban", true)))
+                        }
+                    }
+                }
+            }
+        }
+        .into_string()
+    }
+
+    fn host_action(
+        code: &RoomCode,
+        route: &str,
+        player_id: &PlayerId,
+        label: &str,
+        ban: bool,
+    ) -> String {
+        html! {
+            form class="host-action" hx-post=(Self::room_route(code, route)) hx-swap="none" {
+                input type="hidden" name="player" value=(player_id) {}
+                @if ban {
+                    input type="hidden" name="ban" value="true" {}
+                }
+                button type="submit" { (label) }
+            }
+        }
+        .into_string()
+    }
+
+    fn add_bot_form(code: &RoomCode) -> String {
+        html! {
+            form id="add-bot" hx-post=(Self::room_route(code, "bots")) hx-swap="none" {
+                select name="difficulty" {
+                    @for difficulty in Difficulty::ALL {
+                        option value=(difficulty.value()) selected[difficulty == Difficulty::default()] {
+                            (difficulty.label())
+                        }
+                    }
+                }
+                button type="submit" { "Add bot" }
+            }
+        }
+        .into_string()
+    }
+
+    // Why the host's board or settings couldn't be used
+    pub fn setup_error(message: &str) -> String {
+        html! {
+            div id="setup-error" class="setup-error" { (message) }
+        }
+        .into_string()
+    }
+
+    pub fn ready_button(code: &RoomCode, ready: bool) -> String {
+        html! {
+            form id="ready-button" hx-post=(Self::room_route(code, "ready")) hx-swap="outerHTML" {
+                button type="submit" .ready[ready] {
+                    @if ready { "Not ready" } @else { "Ready" }
+                }
+            }
+        }
+        .into_string()
+    }
+
+    // Everything shown between rounds, rendered for one player since only the host
+    // can start the game and change the settings
+    pub fn lobby_panel(
+        code: &RoomCode,
+        settings: &GameSettings,
+        dictionaries: &Dictionaries,
+        players: &PlayerList,
+        host: Option<&PlayerId>,
+        viewer: &PlayerId,
+        solo: bool,
+    ) -> String {
+        let ready = players.get(viewer).is_some_and(|player| player.ready);
+        let viewer_is_host = host == Some(viewer);
+        html! {
+            div id="game-timer" {
+                (PreEscaped(Self::lobby(code, players, settings.teams, host, viewer)))
+                @if players.contains_key(viewer) {
+                    (PreEscaped(Self::ready_button(code, ready)))
+                    @if settings.team_play() {
+                        form id="team-button" hx-post=(Self::room_route(code, "team")) hx-swap="none" {
+                            button type="submit" { "Switch team" }
+                        }
+                    }
+                }
+                @if viewer_is_host {
+                    div id="setup-error" {}
+                    (PreEscaped(Self::new_game_button(code)))
+                    @if !solo {
+                        (PreEscaped(Self::add_bot_form(code)))
+                    }
+                }
+                (PreEscaped(Self::game_settings(code, settings, dictionaries, viewer_is_host)))
+            }
+        }
+        .into_string()
+    }
+
+    // Takes the place of the lobby in a daily room
+    pub fn daily_panel(
+        code: &RoomCode,
+        date: &str,
+        leaderboard: &[DailyResult],
+        played: bool,
+    ) -> String {
+        html! {
+            div id="game-timer" {
+                div class="daily-title" { "Daily board for " (date) }
+                @if played {
+                    div class="daily-note" { "You've played today's board, come back tomorrow" }
+                } @else {
+                    form hx-post=(Self::room_route(code, "new_game")) hx-swap="none" {
+                        button type="submit" { "Play" }
+                    }
+                    div class="daily-note" { "One try per browser, and it counts as soon as the round starts" }
+                }
+                ol id="daily-leaderboard" {
+                    @for result in leaderboard {
+                        li { (result.username) ": " (result.score) " (" (result.words) " words)" }
+                    }
+                }
+            }
+        }
+        .into_string()
+    }
+
+    // Takes the place of the lobby in a challenge room, comparing the two
+    // players once the board has been played
+    pub fn challenge_panel(code: &RoomCode, challenge: &Challenge, viewer: &PlayerId) -> String {
+        let challenger = challenge.challenger();
+        html! {
+            div id="game-timer" {
+                div class="daily-title" {
+                    (challenger.username) " challenged you: " (challenger.score) " points"
+                }
+                @match challenge.result(viewer) {
+                    Some(result) => (PreEscaped(Self::head_to_head(result, challenger))),
+                    None => {
+                        form hx-post=(Self::room_route(code, "new_game")) hx-swap="none" {
+                            button type="submit" { "Play" }
+                        }
+                    }
+                }
+            }
+        }
+        .into_string()
+    }
+
+    fn head_to_head(result: &ChallengeResult, challenger: &ChallengeResult) -> String {
+        let both: Vec<&String> = result
+            .words
+            .iter()
+            .filter(|word| challenger.words.contains(word))
+            .collect();
+        let only_you: Vec<&String> = result
+            .words
+            .iter()
+            .filter(|word| !challenger.words.contains(word))
+            .collect();
+        let only_them: Vec<&String> = challenger
+            .words
+            .iter()
+            .filter(|word| !result.words.contains(word))
+            .collect();
+        html! {
+            div id="head-to-head" {
+                div class="head-to-head-score" {
+                    "You " (result.score) " – " (challenger.score) " " (challenger.username)
+                }
+                (PreEscaped(Self::compared_words("Both found", &both)))
+                (PreEscaped(Self::compared_words("Only you", &only_you)))
+                (PreEscaped(Self::compared_words(&format!("Only {}", challenger.username), &only_them)))
+            }
+        }
+        .into_string()
+    }
+
+    fn compared_words(title: &str, words: &[&String]) -> String {
+        html! {
+            div class="compared-words" {
+                span class="compared-title" { (title) " (" (words.len()) "): " }
+                (words.iter().map(|word| word.as_str()).collect::<Vec<_>>().join(", "))
+            }
+        }
+        .into_string()
+    }
+
+    pub fn challenge_button(code: &RoomCode) -> String {
+        html! {
+            form id="challenge-link" hx-post=(Self::room_route(code, "challenge")) hx-swap="outerHTML" {
+                button type="submit" { "Challenge a friend" }
+            }
+        }
+        .into_string()
+    }
+
+    pub fn challenge_link(id: &ChallengeId) -> String {
+        let link = format!("/challenge/{}", id);
+        html! {
+            div id="challenge-link" {
+                "Send this link: "
+                a href=(link) { (link) }
+            }
+        }
+        .into_string()
+    }
+
+    // Where a challenge link lands, with everyone who has played it so far
+    pub fn challenge_page(id: &ChallengeId, challenge: &Challenge) -> String {
+        html! {
+            (PreEscaped(Self::render_header()))
+            body {
+                h1 { "Boggle Game" }
+                div id="main-container" {
+                    div class="room-code" {
+                        (challenge.challenger().username) " challenged you"
+                    }
+                    ol id="daily-leaderboard" {
+                        @for result in &challenge.results {
+                            li { (result.username) ": " (result.score) " (" (result.words.len()) " words)" }
+                        }
+                    }
+                    form hx-post=(format!("/challenge/{}", id)) {
+                        button type="submit" { "Play this board" }
+                    }
+                    a href="/" { "Back" }
+                }
+            }
+        }
+        .into_string()
+    }
+
+    pub fn challenge_missing() -> String {
+        html! {
+            (PreEscaped(Self::render_header()))
+            body {
+                h1 { "Boggle Game" }
+                div id="main-container" {
+                    div id="join-error" { "This challenge doesn't exist" }
+                    a href="/" { "Back" }
+                }
+            }
+        }
+        .into_string()
+    }
+
+    // Players who aren't host see the settings without being able to change them
+    fn game_settings(
+        code: &RoomCode,
+        settings: &GameSettings,
+        dictionaries: &Dictionaries,
+        editable: bool,
+    ) -> String {
+        html! {
+            div id="game-settings" {
+                form hx-post=(Self::room_route(code, "settings")) hx-trigger="change" hx-target="#setup-error" hx-swap="outerHTML" {
+                    fieldset disabled[!editable] {
+                    label {
+                        "Time "
+                        select name="duration" {
+                            @for duration in GameSettings::DURATIONS {
+                                option value=(duration) selected[duration == settings.duration] {
+                                    @if duration == GameSettings::UNTIMED {
+                                        "Untimed"
+                                    } @else {
+                                        (Timer::format_time(duration))
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    label {
+                        "Min length "
+                        select name="min_word_length" {
+                            @for length in GameSettings::MIN_WORD_LENGTHS {
+                                option value=(length) selected[length == settings.min_word_length] {
+                                    (length)
+                                }
+                            }
+                        }
+                    }
+                    label {
+                        "Board "
+                        select name="size" {
+                            @for size in BoardSize::ALL {
+                                option value=(size.value()) selected[size == settings.board_size] {
+                                    (size.label())
+                                }
+                            }
+                        }
+                    }
+                    label {
+                        "Dice "
+                        select name="dice" {
+                            @for dice in DiceSet::for_size(settings.board_size) {
+                                option value=(dice.value()) selected[dice == settings.dice] {
+                                    (dice.label())
+                                }
+                            }
+                        }
+                    }
+                    label {
+                        "Dictionary "
+                        select name="dictionary" {
+                            @for name in dictionaries.names() {
+                                option value=(name) selected[name == settings.dictionary] {
+                                    (name)
+                                }
+                            }
+                        }
+                    }
+                    label {
+                        "Scoring "
+                        select name="scoring" {
+                            @for scoring in Scoring::ALL {
+                                option value=(scoring.value()) selected[scoring.value() == settings.scoring.value()] {
+                                    (scoring.label())
+                                }
+                            }
+                        }
+                    }
+                    @if let Scoring::Custom(_) = settings.scoring {
+                        label {
+                            "Points from 3 letters "
+                            input type="text"
+                            name="custom_points"
+                            value=(settings.scoring.custom_points())
+                            title="Comma separated points for 3, 4, 5... letter words; longer words score the last value."
+                            pattern="[0-9]+( *, *[0-9]+)*"
+                            {}
+                        }
+                    }
+                    label {
+                        "Shared words "
+                        select name="scoring_mode" {
+                            @for mode in ScoringMode::ALL {
+                                option value=(mode.value()) selected[mode == settings.scoring_mode] {
+                                    (mode.label())
+                                }
+                            }
+                        }
+                    }
+                    label {
+                        "Scoreboard "
+                        select name="scoreboard" {
+                            @for scoreboard in Scoreboard::ALL {
+                                option value=(scoreboard.value()) selected[scoreboard == settings.scoreboard] {
+                                    (scoreboard.label())
+                                }
+                            }
+                        }
+                    }
+                    label {
+                        "Rounds "
+                        select name="rounds" {
+                            @for rounds in GameSettings::ROUNDS {
+                                option value=(rounds) selected[rounds == settings.rounds] {
+                                    (rounds)
+                                }
+                            }
+                        }
+                    }
+                    label {
+                        "Teams "
+                        select name="teams" {
+                            @for teams in GameSettings::TEAMS {
+                                option value=(teams) selected[teams == settings.teams] {
+                                    @if teams == 1 { "Off" } @else { (teams) }
+                                }
+                            }
+                        }
+                    }
+                    label {
+                        "Hints per round "
+                        select name="hints" {
+                            @for hints in GameSettings::HINTS {
+                                option value=(hints) selected[hints == settings.hints] {
+                                    @if hints == 0 { "Off" } @else { (hints) }
+                                }
+                            }
+                        }
+                    }
+                    (PreEscaped(Self::board_policy_inputs(&settings.board_policy)))
+                    }
+                }
+            }
+        }
+        .into_string()
+    }
+
+    fn board_policy_inputs(policy: &BoardPolicy) -> String {
         html! {
-            form hx-post="/new_game" {
-                button type="submit" { "New Game" }
+            div class="board-policy" {
+                label {
+                    "Min words "
+                    input type="number" name="min_words" min="0" value=[policy.min_words] {}
+                }
+                label {
+                    "Max words "
+                    input type="number" name="max_words" min="0" value=[policy.max_words] {}
+                }
+                label {
+                    "Min board score "
+                    input type="number" name="min_score" min="0" value=[policy.min_score] {}
+                }
+                label {
+                    "Needs a word of length "
+                    input type="number" name="long_word" min="3" max="16" value=[policy.long_word] {}
+                }
             }
         }
         .into_string()
     }
 
-    pub fn word_input() -> String {
+    pub fn word_input(code: &RoomCode, min_word_length: usize) -> String {
         html! {
-            form hx-post="/submit_word" hx-target="#found-words" hx-swap="beforeend" {
+            form hx-post=(Self::room_route(code, "submit_word")) hx-target="#found-words" hx-swap="beforeend" {
                 input type="text"
                 name="word"
                 placeholder="Enter word"
-                title="Enter a single word with only alphabetic characters; 3-16 letters."
-                pattern="[A-Za-z]{3,16}"
-                maxlength="16"
-                minlength="3"
+                title=(format!("Enter a single word with only alphabetic characters; {}-{} letters.", min_word_length, Board::MAX_WORD_LENGTH))
+                pattern=(format!("[A-Za-z]{{{},{}}}", min_word_length, Board::MAX_WORD_LENGTH))
+                maxlength=(Board::MAX_WORD_LENGTH)
+                minlength=(min_word_length)
                 required
                 autofocus
                 {}
@@ -51,44 +561,204 @@ impl Render {
         .into_string()
     }
 
-    pub fn invalid_word_submission() -> String {
+    // Resets the input and shows why the word was or wasn't taken;
+    // only accepted words are appended to the found list
+    pub fn submission(
+        code: &RoomCode,
+        word: &str,
+        result: SubmissionResult,
+        min_word_length: usize,
+    ) -> String {
         html! {
             div id="word-input" hx-swap-oob="true" {
-                (PreEscaped(Self::word_input()))
+                (PreEscaped(Self::word_input(code, min_word_length)))
+                div class=(format!("toast {}", result.value())) {
+                    @if !word.is_empty() {
+                        span class="word" { (word) }
+                        ": "
+                    }
+                    (result.message())
+                }
+            }
+            @if result.is_accepted() {
+                (PreEscaped(Self::word_item(word)))
             }
         }
         .into_string()
     }
 
-    pub fn starting_state() -> String {
+    pub fn starting_state(lobby_panel: &str) -> String {
         html! {
-            div id = "game-timer" {
-                (PreEscaped(Self::new_game_button()))
-            }
+            (PreEscaped(lobby_panel))
+            div id="scoreboard" {}
+            div id="round-controls" {}
             div id="game-board" {}
             div id="word-input" {}
+            div id="hints" {}
             div id="valid-words" {}
-
         }
         .into_string()
     }
 
-    pub fn inprogress_state(timer: &str, board: &Board, player_words: Option<&WordList>) -> String {
+    // Spectators have no words, so they get no word input either
+    pub fn inprogress_state(
+        code: &RoomCode,
+        timer: &str,
+        board: &Board,
+        player_words: Option<&WordList>,
+        players: &PlayerList,
+        settings: &GameSettings,
+        is_host: bool,
+    ) -> String {
         html! {
             div id="game-timer" {
                 (timer)
             }
-            div id="game-board" {
+            (PreEscaped(Self::scoreboard(players, settings)))
+            (PreEscaped(Self::round_controls(code, false, is_host)))
+            div id="game-board" class=(Self::board_class(board)) {
                 (PreEscaped(Self::board(board)))
             }
-            div id="word-input" {
-                (PreEscaped(Self::word_input()))
-            }
-            div id="valid-words" {
-                @if let Some(words) = player_words {
+            @if let Some(words) = player_words {
+                div id="word-input" {
+                    (PreEscaped(Self::word_input(code, settings.min_word_length)))
+                }
+                div id="valid-words" {
                     (PreEscaped(Self::found_words_list(words)))
-                } else {
-                    ul id="found-words" {}
+                }
+            } @else {
+                div id="word-input" {}
+                div id="valid-words" {}
+            }
+        }
+        .into_string()
+    }
+
+    // Leaves the found words alone but hides the board and the word input
+    pub fn paused_state(code: &RoomCode, timer: &str, is_host: bool) -> String {
+        html! {
+            div id="game-timer" {
+                "Paused " (timer)
+            }
+            (PreEscaped(Self::round_controls(code, true, is_host)))
+            div id="game-board" class="paused" {
+                "The board is hidden while the game is paused"
+            }
+            div id="word-input" {}
+            div id="hints" {}
+        }
+        .into_string()
+    }
+
+    // A player's hints this round, with a button for each kind while they have some left
+    pub fn hints(code: &RoomCode, hints: &[Hint], remaining: u32) -> String {
+        html! {
+            div id="hints" {
+                @for hint in hints {
+                    (PreEscaped(Self::hint(hint)))
+                }
+                @if remaining > 0 {
+                    div class="hint-buttons" {
+                        @for kind in HintKind::ALL {
+                            form hx-post=(Self::room_route(code, "hint")) hx-target="#hints" hx-swap="outerHTML" {
+                                input type="hidden" name="kind" value=(kind.value()) {}
+                                button type="submit" { (kind.label()) }
+                            }
+                        }
+                        span class="hint-count" { (remaining) " left" }
+                    }
+                }
+            }
+        }
+        .into_string()
+    }
+
+    // Starting cells use the word path highlighting, so hovering shows the cell
+    fn hint(hint: &Hint) -> String {
+        html! {
+            @match hint {
+                Hint::Letters { start, length } => {
+                    div class="hint" {
+                        "A " (length) "-letter word starts with "
+                        span class="word" { (start) }
+                    }
+                }
+                Hint::Cell(cell) => {
+                    div class="hint" data-path=(Self::cell_key(cell)) {
+                        "A word starts on row " (cell.0 + 1) ", column " (cell.1 + 1)
+                    }
+                }
+                Hint::Counts(counts) => {
+                    div class="hint" {
+                        "Words left: "
+                        (counts
+                            .iter()
+                            .map(|(length, count)| format!("{} × {} letters", count, length))
+                            .collect::<Vec<_>>()
+                            .join(", "))
+                    }
+                }
+            }
+        }
+        .into_string()
+    }
+
+    // Kept outside the timer, which is replaced every second
+    pub fn round_controls(code: &RoomCode, paused: bool, is_host: bool) -> String {
+        html! {
+            div id="round-controls" {
+                @if is_host {
+                    @if paused {
+                        form hx-post=(Self::room_route(code, "resume")) hx-swap="none" {
+                            button type="submit" { "Resume" }
+                        }
+                    } @else {
+                        form hx-post=(Self::room_route(code, "pause")) hx-swap="none" {
+                            button type="submit" { "Pause" }
+                        }
+                    }
+                    form hx-post=(Self::room_route(code, "end")) hx-swap="none" {
+                        button type="submit" { "End round" }
+                    }
+                }
+            }
+        }
+        .into_string()
+    }
+
+    // Broadcast to the room after every accepted word
+    pub fn scoreboard(players: &PlayerList, settings: &GameSettings) -> String {
+        let scoreboard = settings.scoreboard;
+        // Points drop at the end of a cancel round, so say so while they're live
+        let provisional =
+            scoreboard == Scoreboard::Scores && settings.scoring_mode == ScoringMode::CancelShared;
+        html! {
+            div id="scoreboard" {
+                @if scoreboard == Scoreboard::Hidden {
+                    // Blind play shows nothing until the game is over
+                } @else if settings.team_play() {
+                    @for team in players.get_teams_sorted_by_score(settings.teams, &settings.scoring) {
+                        div class="scoreboard-player" {
+                            span class="word" { "Team " (team.number) }
+                            ": " (team.words) " found"
+                            @if scoreboard == Scoreboard::Scores {
+                                ", " (team.score) " pts"
+                            }
+                        }
+                    }
+                } @else {
+                    @for (_, player) in players.get_players_sorted_by_round_score() {
+                        div class="scoreboard-player" {
+                            span class="word" { (player.username) }
+                            ": " (player.words.len()) " found"
+                            @if scoreboard == Scoreboard::Scores {
+                                ", " (player.score) " pts"
+                            }
+                        }
+                    }
+                }
+                @if provisional {
+                    div class="scoreboard-note" { "Provisional, shared words are cancelled when time is up" }
                 }
             }
         }
@@ -98,8 +768,8 @@ impl Render {
     fn found_words_list(found_words: &WordList) -> String {
         html! {
             ul id="found-words" {
-                @for (word, _) in found_words.iter() {
-                    (PreEscaped(Self::word_item(&word)))
+                @for found in found_words.iter() {
+                    (PreEscaped(Self::word_item(&found.word)))
                 }
             }
 
@@ -107,7 +777,17 @@ impl Render {
         .into_string()
     }
 
-    fn word_item(word: &String) -> String {
+    // Appended to the found words of a teammate of whoever found it
+    pub fn team_word(word: &str) -> String {
+        html! {
+            ul id="found-words" hx-swap-oob="beforeend" {
+                (PreEscaped(Self::word_item(word)))
+            }
+        }
+        .into_string()
+    }
+
+    fn word_item(word: &str) -> String {
         html! {
             li {
                 div class="word-container" {
@@ -118,49 +798,132 @@ impl Render {
         .into_string()
     }
 
-    pub fn word_submit(word: String) -> String {
+    pub fn gameover_state(
+        board: &Board,
+        lobby_panel: &str,
+        round_results: &str,
+        found_words: Option<&WordList>,
+    ) -> String {
         html! {
-            div id="word-input" hx-swap-oob="true" {
-                (PreEscaped(Self::word_input()))
+            (PreEscaped(lobby_panel))
+            div id="scoreboard" {}
+            div id="round-controls" {}
+            div id="game-board" class=(Self::board_class(board)) {
+                (PreEscaped(Self::board(board)))
+            }
+            (PreEscaped(round_results))
+            div id="hints" {}
+            div id="valid-words" {
+                (PreEscaped(Self::solved_words(&board.words, found_words)))
             }
-            (PreEscaped(Self::word_item(&word)))
         }
         .into_string()
     }
 
-    pub fn gameover_state(board: &Board, players: &PlayerList) -> String {
-        // Sort players by score in descending order
+    // Scores and standings shown under the board once the round is over
+    pub fn round_results(
+        code: &RoomCode,
+        board: &Board,
+        players: &PlayerList,
+        settings: &GameSettings,
+        round: u32,
+        viewer: &PlayerId,
+        shareable: bool,
+    ) -> String {
+        let found_words = players.get(viewer).map(|player| &player.words);
+        let hints_used = players.get(viewer).map_or(0, |player| player.hints.len());
         html! {
-            div id="game-timer" {
-                (PreEscaped(Self::new_game_button()))
+            div id="word-input" {
+                @if settings.rounds > 1 {
+                    (PreEscaped(Self::match_standings(players, round, settings.rounds)))
+                }
+                @if settings.team_play() {
+                    (PreEscaped(Self::team_scores(players, settings)))
+                }
+                @if let Some(found_words) = found_words {
+                    div class="found-summary" {
+                        "You found " (found_words.len()) " of " (board.words.len()) " words"
+                        @if hints_used > 0 { " with " (hints_used) " hints" }
+                    }
+                }
+                (PreEscaped(Self::board_info(board, settings)))
+                @if shareable && found_words.is_some() {
+                    (PreEscaped(Self::challenge_button(code)))
+                }
+                (PreEscaped(Self::player_scores(code, board, players)))
             }
-            div id="game-board" {
-                (PreEscaped(Self::board(&board)))
+        }
+        .into_string()
+    }
+
+    // Totals across the rounds played so far, or the final result once the match is over
+    fn match_standings(players: &PlayerList, round: u32, rounds: u32) -> String {
+        html! {
+            div class="match-standings" {
+                @if round >= rounds {
+                    div class="match-title" { "Final standings" }
+                } @else {
+                    div class="match-title" { "After round " (round) " of " (rounds) }
+                }
+                ol {
+                    @for (_, player) in players.get_players_sorted_by_match_score() {
+                        li { (player.username) ": " (player.match_score) }
+                    }
+                }
             }
-            div id="word-input" {
-                (PreEscaped(Self::player_scores(&board, &players)))
+        }
+        .into_string()
+    }
+
+    fn team_scores(players: &PlayerList, settings: &GameSettings) -> String {
+        html! {
+            div class="team-scores" {
+                @for team in players.get_teams_sorted_by_score(settings.teams, &settings.scoring) {
+                    div class="team-score" {
+                        span class="word" { "Team " (team.number) ": " (team.total_score) }
+                        " ("
+                        @for (i, member) in team.members.iter().enumerate() {
+                            @if i > 0 { ", " }
+                            (member)
+                        }
+                        ")"
+                    }
+                }
             }
-            div id="valid-words" {
-                (PreEscaped(Self::valid_words(&board.words)))
+        }
+        .into_string()
+    }
+
+    fn board_info(board: &Board, settings: &GameSettings) -> String {
+        html! {
+            div class="board-info" {
+                @if let Some(dice) = board.dice {
+                    div { "Dice: " (dice.label()) }
+                }
+                @if let Some(seed) = board.seed {
+                    div { "Seed: " (seed) }
+                }
+                div { "Board: " (board.notation()) }
+                div { "Scoring: " (settings.scoring.label()) }
             }
         }
         .into_string()
     }
 
-    fn player_scores(board: &Board, players: &PlayerList) -> String {
+    fn player_scores(code: &RoomCode, board: &Board, players: &PlayerList) -> String {
         let sorted_players = players.get_players_sorted_by_score();
         html! {
-            (PreEscaped(Self::scores("Board Total".to_string(), "Board Total".to_string(), board.words.total_score.to_string())))
+            (PreEscaped(Self::scores(code, "Board Total".to_string(), "Board Total".to_string(), board.words.total_score.to_string())))
             @for (player_id, player) in sorted_players {
-                (PreEscaped(Self::scores(player_id.to_string(), player.username.to_string(), player.words.total_score.to_string())))
+                (PreEscaped(Self::scores(code, player_id.to_string(), player.username.to_string(), player.words.total_score.to_string())))
             }
         }
         .into_string()
     }
 
-    fn scores(player_id: String, name: String, score: String) -> String {
+    fn scores(code: &RoomCode, player_id: String, name: String, score: String) -> String {
         html! {
-            form action="/get_player_score" method="post" hx-post="/get_score" hx-trigger="click" hx-target="#valid-words" {
+            form hx-post=(Self::room_route(code, "get_score")) hx-trigger="click" hx-target="#valid-words" {
                 input type="hidden" name="username" value=(player_id) {}
                 div class="player-container"  {
                     (name) ": " (score)
@@ -170,17 +933,16 @@ impl Render {
         .into_string()
     }
 
+    fn board_class(board: &Board) -> String {
+        format!("board-{}", board.size.dimension())
+    }
+
     fn board(board: &Board) -> String {
         html! {
-            @for row in &board.board {
-                @for &letter in row {
-                    div class="board-cell" {
-                        // Check if the letter is 'Q' and display "Qu" instead
-                        @if letter == 'Q' {
-                            "Qu"
-                        } @else {
-                            (letter)
-                        }
+            @for (i, row) in board.board.iter().enumerate() {
+                @for (j, tile) in row.iter().enumerate() {
+                    div class="board-cell" data-cell=(Self::cell_key(&(i, j))) {
+                        (tile)
                     }
                 }
             }
@@ -188,14 +950,33 @@ impl Render {
         .into_string()
     }
 
+    fn cell_key((row, col): &Cell) -> String {
+        format!("{}-{}", row, col)
+    }
+
+    // Cells in the order they spell the word, read by static/boggle.js to highlight the path
+    fn path_attribute(path: &[Cell]) -> String {
+        path.iter()
+            .map(Self::cell_key)
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
     pub fn valid_words(word_list: &WordList) -> String {
+        Self::solved_words(word_list, None)
+    }
+
+    // Every word on the board, marking the ones the player missed
+    fn solved_words(word_list: &WordList, player_words: Option<&WordList>) -> String {
         html! {
            ul {
-               @for (word, definition) in word_list.iter() {
+               @for found in word_list.iter() {
+                   @let missed = player_words.is_some_and(|words| !words.contains(&found.word));
                    li {
-                       div class="word-container" {
-                           span class="word" { (word) }
-                           span class="definition" { (definition) }
+                       div.word-container.cancelled[found.cancelled].missed[missed] data-path=(Self::path_attribute(&found.path)) {
+                           span class="word" { (found.word) }
+                           span class="points" { (found.points) }
+                           span class="definition" { (found.definition) }
                        }
                    }
                }
@@ -209,19 +990,97 @@ impl Render {
             (PreEscaped(Self::render_header()))
             body {
                 h1 { "Boggle Game" }
-                (PreEscaped(Self::shell_template()))
+                div id="main-container" {
+                    (PreEscaped(Self::room_menu()))
+                }
+            }
+        }
+        .into_string()
+    }
+
+    fn room_menu() -> String {
+        html! {
+            div id="room-menu" {
+                form hx-post="/rooms" {
+                    button type="submit" { "Create Room" }
+                }
+                form hx-post="/solo" {
+                    button type="submit" { "Play Solo" }
+                }
+                form hx-post="/daily" {
+                    button type="submit" { "Daily Board" }
+                }
+                form hx-post="/join" hx-target="#join-error" {
+                    input type="text"
+                    name="code"
+                    placeholder="Join code"
+                    title="Enter the 4 letter code of the room."
+                    pattern="[A-Za-z]{4}"
+                    maxlength="4"
+                    minlength="4"
+                    required
+                    {}
+                    button type="submit" { "Join" }
+                    button type="submit" name="spectate" value="true" { "Watch" }
+                }
+                div id="join-error" {}
+            }
+        }
+        .into_string()
+    }
+
+    pub fn room_not_found(code: &RoomCode) -> String {
+        html! {
+            "No room found with code " (code)
+        }
+        .into_string()
+    }
+
+    pub fn room_missing(code: &RoomCode) -> String {
+        html! {
+            (PreEscaped(Self::render_header()))
+            body {
+                h1 { "Boggle Game" }
+                div id="main-container" {
+                    div id="join-error" {
+                        (PreEscaped(Self::room_not_found(code)))
+                    }
+                    a href="/" { "Back" }
+                }
             }
         }
         .into_string()
     }
 
-    pub fn shell_template() -> String {
+    pub fn room(code: &RoomCode, spectate: bool) -> String {
         html! {
             (PreEscaped(Self::render_header()))
-            div id="game-container" hx-ext="ws" ws-connect="/ws" {
+            body {
+                h1 { "Boggle Game" }
+                div class="room-code" {
+                    "Room " (code)
+                    @if spectate { " (watching)" }
+                }
+                (PreEscaped(Self::shell_template(code, spectate)))
+            }
+        }
+        .into_string()
+    }
+
+    pub fn shell_template(code: &RoomCode, spectate: bool) -> String {
+        let ws_route = if spectate {
+            Self::room_route(code, "ws?spectate=true")
+        } else {
+            Self::room_route(code, "ws")
+        };
+        html! {
+            div id="game-container" hx-ext="ws" ws-connect=(ws_route) {
                 div id="game-timer" {}
+                div id="scoreboard" {}
+                div id="round-controls" {}
                 div id="game-board" {}
                 div id="word-input" {}
+                div id="hints" {}
                 div id="valid-words" {}
             }
         }
@@ -239,6 +1098,7 @@ impl Render {
                         integrity="sha384-QFjmbokDn2DjBjq+fM+8LUIVrAgqcNW2s0PjAxHETgRn9l4fvX31ZxDxvwQnyMOX"
                         crossorigin="anonymous" {}
                     script src="https://unpkg.com/htmx.org/dist/ext/ws.js" {}
+                    script src="/static/boggle.js" defer {}
                     link rel="stylesheet" href="/static/style.css";
                 }
             }
@@ -0,0 +1,62 @@
+// Highlight the cells of a word on the board when it is hovered or clicked
+(function () {
+    let pinned = null;
+
+    function clearHighlight() {
+        document.querySelectorAll(".board-cell.highlight").forEach(function (cell) {
+            cell.classList.remove("highlight");
+            delete cell.dataset.step;
+        });
+    }
+
+    function highlight(container) {
+        clearHighlight();
+        container.dataset.path.split(" ").forEach(function (key, index) {
+            const cell = document.querySelector('.board-cell[data-cell="' + key + '"]');
+            if (cell) {
+                cell.classList.add("highlight");
+                cell.dataset.step = index + 1;
+            }
+        });
+    }
+
+    document.addEventListener("mouseover", function (event) {
+        const container = event.target.closest("[data-path]");
+        if (container) {
+            highlight(container);
+        }
+    });
+
+    document.addEventListener("mouseout", function (event) {
+        const container = event.target.closest("[data-path]");
+        if (container && !container.contains(event.relatedTarget)) {
+            if (pinned && document.body.contains(pinned)) {
+                highlight(pinned);
+            } else {
+                clearHighlight();
+            }
+        }
+    });
+
+    // Clicking a word keeps its path on the board until another word is clicked
+    document.addEventListener("click", function (event) {
+        const container = event.target.closest("[data-path]");
+        if (container) {
+            pinned = pinned === container ? null : container;
+            if (pinned) {
+                highlight(pinned);
+            } else {
+                clearHighlight();
+            }
+        }
+    });
+})();
+
+// Show the host why a board or settings change was turned down, instead of
+// htmx dropping the error response
+document.addEventListener("htmx:beforeSwap", function (event) {
+    if (event.detail.xhr.status === 422 && event.detail.target.id === "setup-error") {
+        event.detail.shouldSwap = true;
+        event.detail.isError = false;
+    }
+});